@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::types::{Entry, FeedId};
+
+// renders entries as an Atom feed, `feeds` maps feed IDs to their titles
+pub fn render_feed(
+    title: &str,
+    self_url: &str,
+    entries: &[Entry<'_>],
+    feeds: &HashMap<FeedId, String>,
+) -> String {
+    let updated = entries
+        .iter()
+        .map(|entry| entry.published)
+        .max()
+        .unwrap_or_else(OffsetDateTime::now_utc);
+
+    let mut out = String::new();
+    out.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    out.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    write_element(&mut out, "id", self_url);
+    write_element(&mut out, "title", title);
+    write_element(&mut out, "updated", &format_date(updated));
+    write_element(&mut out, "generator", "grunt");
+    out.push_str(r#"<link rel="self" type="application/atom+xml" href=""#);
+    escape_xml(self_url, &mut out);
+    out.push_str(r#""/>"#);
+
+    for entry in entries {
+        out.push_str("<entry>");
+        write_element(&mut out, "id", &format!("urn:grunt:entry:{}", entry.id));
+        write_element(&mut out, "title", entry.title.unwrap_or_default());
+        write_element(&mut out, "updated", &format_date(entry.published));
+        write_element(&mut out, "published", &format_date(entry.published));
+        if let Some(url) = entry.url {
+            out.push_str(r#"<link rel="alternate" type="text/html" href=""#);
+            escape_xml(url, &mut out);
+            out.push_str(r#""/>"#);
+        }
+        if let Some(author) = entry.author {
+            out.push_str("<author>");
+            write_element(&mut out, "name", author);
+            out.push_str("</author>");
+        }
+        if let Some(feed) = feeds.get(&entry.feed_id) {
+            out.push_str("<source>");
+            write_element(&mut out, "title", feed);
+            out.push_str("</source>");
+        }
+        if let Some(summary) = entry.summary {
+            out.push_str(r#"<summary type="html">"#);
+            escape_xml(summary, &mut out);
+            out.push_str("</summary>");
+        }
+        if let Some(content) = entry.content {
+            out.push_str(r#"<content type="html">"#);
+            escape_xml(content, &mut out);
+            out.push_str("</content>");
+        }
+        out.push_str("</entry>");
+    }
+    out.push_str("</feed>");
+    out
+}
+
+fn write_element(out: &mut String, name: &str, text: &str) {
+    let _ = write!(out, "<{name}>");
+    escape_xml(text, out);
+    let _ = write!(out, "</{name}>");
+}
+
+fn format_date(date: OffsetDateTime) -> String {
+    date.format(&Rfc3339).unwrap_or_default()
+}
+
+fn escape_xml(str: &str, out: &mut String) {
+    for char in str.chars() {
+        match char {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // characters that are not allowed in XML documents
+            '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' => {}
+            _ => out.push(char),
+        }
+    }
+}
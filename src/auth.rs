@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::body::BoxBody;
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::{HeaderValue, Method, Request, Response, StatusCode};
+use axum::response::IntoResponse;
+use futures_util::future::BoxFuture;
+use ring::constant_time;
+use ring::rand::{SecureRandom, SystemRandom};
+use sled_bincode::Value;
+use time::OffsetDateTime;
+use tower_http::auth::AsyncAuthorizeRequest;
+
+use crate::digest::{md5_hex, random_token, sha256_hex};
+use crate::repo::{Repo, Store};
+use crate::result::{Result, ServiceEror};
+use crate::types::{Account, ApiToken, TokenScope};
+
+// returns `false` when the user already exists
+pub fn create_user(store: &Store, name: &str, password: &str) -> Result<bool> {
+    if store.get_account(name)?.is_some() {
+        return Ok(false);
+    }
+    let credentials = Credentials::new(password)?;
+    store.set_account(&Account {
+        name,
+        password_hash: &credentials.password_hash,
+        fever_key: "",
+        reader_token: &credentials.reader_token,
+        disabled: false,
+        created_at: OffsetDateTime::now_utc(),
+    })?;
+    Ok(true)
+}
+
+// returns `false` when the user does not exist
+pub fn set_password(store: &Store, name: &str, password: &str) -> Result<bool> {
+    let res = match store.get_account(name)? {
+        Some(res) => res,
+        None => return Ok(false),
+    };
+    let account = res.value()?;
+    let admin_password = match store.get_admin_password(name)? {
+        Some(res) => check_hash(res.value()?, password),
+        None => false,
+    };
+    if admin_password || secrets_match(account.fever_key, &fever_key(name, password)) {
+        return Err(ServiceEror::ReusedPassword);
+    }
+    let credentials = Credentials::new(password)?;
+    store.set_account(&Account {
+        password_hash: &credentials.password_hash,
+        reader_token: &credentials.reader_token,
+        ..account
+    })?;
+    Ok(true)
+}
+
+// the Fever API key is an unsalted MD5 hash, so it's derived from a separate password that
+// only grants access to the Fever API, returns `false` when the user does not exist
+pub fn set_fever_password(store: &Store, name: &str, password: &str) -> Result<bool> {
+    let res = match store.get_account(name)? {
+        Some(res) => res,
+        None => return Ok(false),
+    };
+    let account = res.value()?;
+    if check_password(&account, password) {
+        return Err(ServiceEror::ReusedPassword);
+    }
+    store.set_account(&Account {
+        fever_key: &fever_key(name, password),
+        ..account
+    })?;
+    Ok(true)
+}
+
+// returns `false` when the user does not exist or has no Fever password
+pub fn remove_fever_password(store: &Store, name: &str) -> Result<bool> {
+    let res = match store.get_account(name)? {
+        Some(res) => res,
+        None => return Ok(false),
+    };
+    let account = res.value()?;
+    if account.fever_key.is_empty() {
+        return Ok(false);
+    }
+    store.set_account(&Account {
+        fever_key: "",
+        ..account
+    })?;
+    Ok(true)
+}
+
+fn fever_key(name: &str, password: &str) -> String {
+    md5_hex(format!("{name}:{password}").as_bytes())
+}
+
+// returns `false` when the user does not exist
+pub fn set_disabled(store: &Store, name: &str, disabled: bool) -> Result<bool> {
+    let res = match store.get_account(name)? {
+        Some(res) => res,
+        None => return Ok(false),
+    };
+    store.set_account(&Account {
+        disabled,
+        ..res.value()?
+    })?;
+    Ok(true)
+}
+
+// a password given in plain text or as an Argon2 hash in the PHC string format
+pub enum Password {
+    Plain(String),
+    Hashed(String),
+}
+
+// creates the user or updates their password when it doesn't match
+pub fn ensure_user(store: &Store, name: &str, password: &Password) -> Result<()> {
+    let account = store.get_account(name)?;
+    let account = account.as_ref().map(Value::value).transpose()?;
+    match (password, account) {
+        (Password::Plain(password), Some(account)) if !check_password(&account, password) => {
+            set_password(store, name, password).map(drop)
+        }
+        (Password::Plain(password), None) => create_user(store, name, password).map(drop),
+        (Password::Hashed(hash), Some(account)) if account.password_hash != hash => {
+            set_password_hash(store, name, hash)
+        }
+        (Password::Hashed(hash), None) => set_password_hash(store, name, hash),
+        _ => Ok(()),
+    }
+}
+
+fn set_password_hash(store: &Store, name: &str, hash: &str) -> Result<()> {
+    PasswordHash::new(hash)?;
+    let reader_token = random_token()?;
+    match store.get_account(name)? {
+        Some(res) => store.set_account(&Account {
+            password_hash: hash,
+            reader_token: &reader_token,
+            ..res.value()?
+        }),
+        None => store.set_account(&Account {
+            name,
+            password_hash: hash,
+            fever_key: "",
+            reader_token: &reader_token,
+            disabled: false,
+            created_at: OffsetDateTime::now_utc(),
+        }),
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String> {
+    let mut salt = [0u8; 16];
+    SystemRandom::new().fill(&mut salt)?;
+    let salt = SaltString::b64_encode(&salt)?;
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+// returns `true` when the credentials belong to an enabled account
+pub fn verify_password(store: &Store, name: &str, password: &str) -> Result<bool> {
+    match store.get_account(name)? {
+        Some(res) if !res.value()?.disabled => Ok(check_password(&res.value()?, password)),
+        _ => {
+            check_dummy_hash(password);
+            Ok(false)
+        }
+    }
+}
+
+pub fn find_fever_user(store: &Store, api_key: &str) -> Result<Option<String>> {
+    find_user(store, |account| secrets_match(account.fever_key, api_key))
+}
+
+pub fn find_reader_user(store: &Store, token: &str) -> Result<Option<String>> {
+    find_user(store, |account| secrets_match(account.reader_token, token))
+}
+
+fn find_user<F: Fn(&Account) -> bool>(store: &Store, matches: F) -> Result<Option<String>> {
+    for res in store.get_accounts()? {
+        let account = res.value()?;
+        if !account.disabled && matches(&account) {
+            return Ok(Some(account.name.to_owned()));
+        }
+    }
+    Ok(None)
+}
+
+// empty secrets are never valid, accounts without a Fever password have no Fever API key
+fn secrets_match(expected: &str, actual: &str) -> bool {
+    !expected.is_empty()
+        && constant_time::verify_slices_are_equal(expected.as_bytes(), actual.as_bytes()).is_ok()
+}
+
+// unknown and disabled accounts take as long to reject as wrong passwords, so that the timing
+// of a login doesn't reveal which accounts exist
+fn check_dummy_hash(password: &str) {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = DUMMY_HASH.get_or_init(|| hash_password("").unwrap_or_default());
+    check_hash(hash, password);
+}
+
+fn check_password(account: &Account, password: &str) -> bool {
+    check_hash(account.password_hash, password)
+}
+
+// the hashes are compared in constant time
+fn check_hash(password_hash: &str, password: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+// everything that is reset along with the password of an account
+struct Credentials {
+    password_hash: String,
+    reader_token: String,
+}
+
+impl Credentials {
+    fn new(password: &str) -> Result<Self> {
+        Ok(Self {
+            password_hash: hash_password(password)?,
+            reader_token: random_token()?,
+        })
+    }
+}
+
+// returns the ID and the secret of the new token, only a hash of the secret is stored
+pub fn create_api_token(
+    store: &Store,
+    user: &str,
+    name: &str,
+    scope: TokenScope,
+) -> Result<(String, String)> {
+    let token = random_token()?;
+    let id = sha256_hex(token.as_bytes());
+    store.add_api_token(&id, &ApiToken {
+        user,
+        name,
+        scope,
+        created_at: OffsetDateTime::now_utc(),
+    })?;
+    Ok((id, token))
+}
+
+// returns the user of the token and the scope it grants when the account is enabled,
+// admin tokens only grant write access to users without an admin password
+pub fn find_token_user(store: &Store, token: &str) -> Result<Option<(String, TokenScope)>> {
+    let res = match store.get_api_token(&sha256_hex(token.as_bytes()))? {
+        Some(res) => res,
+        None => return Ok(None),
+    };
+    let token = res.value()?;
+    match store.get_account(token.user)? {
+        Some(account) if !account.value()?.disabled => {}
+        _ => return Ok(None),
+    }
+    let scope = if token.scope == TokenScope::Admin && store.get_admin_password(token.user)?.is_none() {
+        TokenScope::Write
+    } else {
+        token.scope
+    };
+    Ok(Some((token.user.to_owned(), scope)))
+}
+
+// the admin password has to differ from the password of the account, otherwise reader clients
+// would hold admin credentials, returns `false` when the user does not exist
+pub fn set_admin_password(store: &Store, name: &str, password: &str) -> Result<bool> {
+    let res = match store.get_account(name)? {
+        Some(res) => res,
+        None => return Ok(false),
+    };
+    if check_password(&res.value()?, password) {
+        return Err(ServiceEror::ReusedPassword);
+    }
+    store.set_admin_password(name, &hash_password(password)?)?;
+    Ok(true)
+}
+
+// sets the admin password of an existing user when it doesn't match, an admin password that was
+// stored along with the same account password is rejected as well
+pub fn ensure_admin_password(store: &Store, name: &str, password: &str) -> Result<()> {
+    if let Some(res) = store.get_account(name)? {
+        if check_password(&res.value()?, password) {
+            return Err(ServiceEror::ReusedPassword);
+        }
+    }
+    let matches = match store.get_admin_password(name)? {
+        Some(res) => check_hash(res.value()?, password),
+        None => false,
+    };
+    if !matches {
+        set_admin_password(store, name, password)?;
+    }
+    Ok(())
+}
+
+// returns the scope granted to the credentials of an enabled account, the account password
+// grants write access and the admin password grants admin access
+pub fn verify_credentials(
+    store: &Store,
+    name: &str,
+    password: &str,
+    scope: TokenScope,
+) -> Result<Option<TokenScope>> {
+    let account = match store.get_account(name)? {
+        Some(res) if !res.value()?.disabled => res,
+        _ => {
+            check_dummy_hash(password);
+            return Ok(None);
+        }
+    };
+    let account = account.value()?;
+    if scope < TokenScope::Admin && check_password(&account, password) {
+        return Ok(Some(TokenScope::Write));
+    }
+    match store.get_admin_password(name)? {
+        Some(res) if check_hash(res.value()?, password) => Ok(Some(TokenScope::Admin)),
+        _ => Ok(None),
+    }
+}
+
+// how long checked BasicAuth credentials are remembered, clients send them with every request
+// and checking an Argon2 hash is deliberately slow
+const CREDENTIALS_TTL: Duration = Duration::from_secs(5 * 60);
+const MAX_CACHED_CREDENTIALS: usize = 1024;
+
+// credentials that were accepted keyed by the SHA-256 hash of the header and the required scope,
+// failed checks are never cached, so that they can't push out the credentials of other users
+#[derive(Default)]
+struct CredentialsCache {
+    entries: Mutex<HashMap<(String, TokenScope), CachedCredentials>>,
+}
+
+struct CachedCredentials {
+    granted: TokenScope,
+    stamp: AccountStamp,
+    expires_at: Instant,
+}
+
+// the state of the account the credentials were checked against, cached results are discarded
+// as soon as the account is disabled or one of its passwords changes
+#[derive(PartialEq, Eq)]
+struct AccountStamp {
+    disabled: bool,
+    password_hash: String,
+    admin_password_hash: Option<String>,
+}
+
+impl AccountStamp {
+    fn load(store: &Store, name: &str) -> Result<Option<Self>> {
+        let res = match store.get_account(name)? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        let account = res.value()?;
+        let admin_password_hash = match store.get_admin_password(name)? {
+            Some(res) => Some(res.value()?.to_owned()),
+            None => None,
+        };
+        Ok(Some(Self {
+            disabled: account.disabled,
+            password_hash: account.password_hash.to_owned(),
+            admin_password_hash,
+        }))
+    }
+}
+
+impl CredentialsCache {
+    fn verify(
+        &self,
+        store: &Store,
+        header: &str,
+        name: &str,
+        password: &str,
+        scope: TokenScope,
+    ) -> Result<Option<TokenScope>> {
+        let stamp = AccountStamp::load(store, name)?;
+        let key = (sha256_hex(header.as_bytes()), scope);
+        let now = Instant::now();
+        if let Some(stamp) = &stamp {
+            if let Some(cached) = self.entries.lock().unwrap().get(&key) {
+                if cached.expires_at > now && cached.stamp == *stamp {
+                    return Ok(Some(cached.granted));
+                }
+            }
+        }
+
+        let granted = verify_credentials(store, name, password, scope)?;
+        if let (Some(granted), Some(stamp)) = (granted, stamp) {
+            let mut entries = self.entries.lock().unwrap();
+            entries.retain(|_, cached| cached.expires_at > now);
+            if entries.len() >= MAX_CACHED_CREDENTIALS {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.expires_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+            entries.insert(key, CachedCredentials {
+                granted,
+                stamp,
+                expires_at: now + CREDENTIALS_TTL,
+            });
+        }
+        Ok(granted)
+    }
+}
+
+// checks BasicAuth credentials or API tokens and makes the repo of the user and the granted scope
+// available to handlers
+#[derive(Clone)]
+pub struct UserAuth {
+    store: Arc<Store>,
+    scope: TokenScope,
+    tokens: bool,
+    cache: Arc<CredentialsCache>,
+}
+
+impl UserAuth {
+    pub fn basic(store: Arc<Store>) -> Self {
+        Self {
+            store,
+            scope: TokenScope::Write,
+            tokens: false,
+            cache: Arc::default(),
+        }
+    }
+
+    // routes that require the admin scope can only be used with the admin password or admin tokens
+    pub fn with_tokens(store: Arc<Store>, scope: TokenScope) -> Self {
+        Self {
+            store,
+            scope,
+            tokens: true,
+            cache: Arc::default(),
+        }
+    }
+
+    // read-only tokens can make safe requests, unless the routes require the admin scope
+    fn required_scope<B>(&self, request: &Request<B>) -> TokenScope {
+        let safe = request.method() == Method::GET || request.method() == Method::HEAD;
+        if safe && self.scope < TokenScope::Admin {
+            TokenScope::Read
+        } else {
+            self.scope
+        }
+    }
+
+    fn get_user(&self, header: Option<&str>, required: TokenScope) -> Result<Option<(String, TokenScope)>> {
+        let header = match header {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let granted = match header.strip_prefix("Bearer ") {
+            Some(token) if self.tokens => find_token_user(&self.store, token)?,
+            _ => match basic_credentials(header) {
+                Some((user, password)) => self
+                    .cache
+                    .verify(&self.store, header, &user, &password, required)?
+                    .map(|scope| (user, scope)),
+                None => None,
+            },
+        };
+        Ok(granted.filter(|(_, scope)| *scope >= required))
+    }
+
+    fn get_repo(&self, header: Option<&str>, required: TokenScope) -> Result<Option<(Arc<Repo>, TokenScope)>> {
+        match self.get_user(header, required)? {
+            Some((user, scope)) => Ok(self.store.get_repo(&user)?.map(|repo| (repo, scope))),
+            None => Ok(None),
+        }
+    }
+}
+
+// the credentials are checked on the blocking thread pool, because Argon2 is deliberately slow
+impl<B: Send + 'static> AsyncAuthorizeRequest<B> for UserAuth {
+    type RequestBody = B;
+    type ResponseBody = BoxBody;
+    type Future = BoxFuture<'static, Result<Request<B>, Response<BoxBody>>>;
+
+    fn authorize(&mut self, mut request: Request<B>) -> Self::Future {
+        let auth = self.clone();
+        let required = self.required_scope(&request);
+        let header = authorization(&request).map(str::to_owned);
+        Box::pin(async move {
+            let granted = tokio::task::spawn_blocking(move || auth.get_repo(header.as_deref(), required))
+                .await
+                .map_err(ServiceEror::from)
+                .and_then(|res| res)
+                .map_err(IntoResponse::into_response)?;
+            match granted {
+                Some((repo, scope)) => {
+                    request.extensions_mut().insert(repo);
+                    request.extensions_mut().insert(scope);
+                    Ok(request)
+                }
+                None => {
+                    let mut res = StatusCode::UNAUTHORIZED.into_response();
+                    res.headers_mut()
+                        .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Basic"));
+                    Err(res)
+                }
+            }
+        })
+    }
+}
+
+fn authorization<B>(request: &Request<B>) -> Option<&str> {
+    request.headers().get(AUTHORIZATION)?.to_str().ok()
+}
+
+fn basic_credentials(header: &str) -> Option<(String, String)> {
+    let encoded = header.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(base64::decode(encoded).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_owned(), password.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_store() -> (tempfile::TempDir, Arc<Store>) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(Store::new(dir.path()).unwrap());
+        (dir, store)
+    }
+
+    fn basic_header(user: &str, password: &str) -> String {
+        format!("Basic {}", base64::encode(format!("{user}:{password}")))
+    }
+
+    fn verify(cache: &CredentialsCache, store: &Store, user: &str, password: &str) -> Option<TokenScope> {
+        let header = basic_header(user, password);
+        cache
+            .verify(store, &header, user, password, TokenScope::Write)
+            .unwrap()
+    }
+
+    #[test]
+    fn only_accepted_credentials_are_cached() {
+        let (_dir, store) = open_store();
+        create_user(&store, "alice", "secret").unwrap();
+        let cache = CredentialsCache::default();
+
+        assert_eq!(verify(&cache, &store, "alice", "wrong"), None);
+        assert_eq!(verify(&cache, &store, "nobody", "secret"), None);
+        assert!(cache.entries.lock().unwrap().is_empty());
+        assert_eq!(verify(&cache, &store, "alice", "secret"), Some(TokenScope::Write));
+        assert_eq!(cache.entries.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cached_credentials_are_dropped_when_the_account_changes() {
+        let (_dir, store) = open_store();
+        create_user(&store, "alice", "secret").unwrap();
+        let cache = CredentialsCache::default();
+
+        assert_eq!(verify(&cache, &store, "alice", "secret"), Some(TokenScope::Write));
+        set_password(&store, "alice", "changed").unwrap();
+        assert_eq!(verify(&cache, &store, "alice", "secret"), None);
+
+        assert_eq!(verify(&cache, &store, "alice", "changed"), Some(TokenScope::Write));
+        set_disabled(&store, "alice", true).unwrap();
+        assert_eq!(verify(&cache, &store, "alice", "changed"), None);
+    }
+
+    #[test]
+    fn the_oldest_credentials_are_evicted_when_the_cache_is_full() {
+        let (_dir, store) = open_store();
+        create_user(&store, "alice", "secret").unwrap();
+        let cache = CredentialsCache::default();
+        let now = Instant::now();
+        {
+            let mut entries = cache.entries.lock().unwrap();
+            for i in 0..MAX_CACHED_CREDENTIALS {
+                let stamp = AccountStamp {
+                    disabled: false,
+                    password_hash: String::new(),
+                    admin_password_hash: None,
+                };
+                entries.insert((i.to_string(), TokenScope::Write), CachedCredentials {
+                    granted: TokenScope::Write,
+                    stamp,
+                    expires_at: now + CREDENTIALS_TTL + Duration::from_secs(i as u64),
+                });
+            }
+        }
+
+        assert_eq!(verify(&cache, &store, "alice", "secret"), Some(TokenScope::Write));
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.len(), MAX_CACHED_CREDENTIALS);
+        assert!(!entries.contains_key(&("0".to_owned(), TokenScope::Write)));
+        let key = (sha256_hex(basic_header("alice", "secret").as_bytes()), TokenScope::Write);
+        assert!(entries.contains_key(&key));
+    }
+}
@@ -21,3 +21,19 @@ pub mod rfc3339_date {
             .map_err(serde::de::Error::custom)
     }
 }
+
+pub mod rfc3339_date_opt {
+    use serde::{Deserialize, Deserializer};
+    use time::{format_description, OffsetDateTime};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<OffsetDateTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // query parameters may be percent-encoded, so they can't always be borrowed
+        Option::<String>::deserialize(deserializer)?
+            .map(|str| OffsetDateTime::parse(&str, &format_description::well_known::Rfc3339))
+            .transpose()
+            .map_err(serde::de::Error::custom)
+    }
+}
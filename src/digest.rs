@@ -0,0 +1,23 @@
+use md5::{Digest, Md5};
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+
+// only used to derive API keys and identifiers of protocols that require MD5, never to store passwords
+pub fn md5_hex(input: &[u8]) -> String {
+    to_hex(&Md5::digest(input))
+}
+
+// a hex encoded random token suitable for use in URLs
+pub fn random_token() -> Result<String, ring::error::Unspecified> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new().fill(&mut bytes)?;
+    Ok(to_hex(&bytes))
+}
+
+pub fn sha256_hex(input: &[u8]) -> String {
+    to_hex(digest::digest(&digest::SHA256, input).as_ref())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
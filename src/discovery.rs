@@ -0,0 +1,95 @@
+use hyper::Uri;
+use serde::Serialize;
+
+use crate::fetch::{resolve_url, HttpClient, Page};
+use crate::html::{self, Token};
+use crate::refresh::parse_feed;
+use crate::result::Result;
+
+// JSON Feed is left out, the feed parser can't read it
+const FEED_TYPES: [&str; 2] = ["application/rss+xml", "application/atom+xml"];
+// locations commonly used by blog engines, tried when a page doesn't advertise any feeds
+const COMMON_PATHS: [&str; 5] = ["/feed", "/rss.xml", "/atom.xml", "/feed.xml", "/index.xml"];
+
+#[derive(Debug, Serialize)]
+pub struct FeedLink {
+    pub feed_url: String,
+    pub title: Option<String>,
+}
+
+// looks up the feeds advertised by a website
+pub async fn discover_feeds(client: &HttpClient, url: &str) -> Result<Vec<FeedLink>> {
+    let page = client.get(url).await?;
+    let links = if page.is_html() {
+        find_feed_links(&page)
+    } else {
+        vec![]
+    };
+    if !links.is_empty() {
+        return Ok(links);
+    }
+    Ok(probe_common_paths(client, &page.url).await.into_iter().collect())
+}
+
+fn find_feed_links(page: &Page) -> Vec<FeedLink> {
+    let mut links: Vec<FeedLink> = vec![];
+    let mut page_title = None;
+    let text = page.text();
+    let mut tokens = html::tokenize(&text).peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Start(tag) if tag.is("title") => {
+                if let Some(Token::Text(str)) = tokens.peek() {
+                    page_title = Some(html::decode_entities(str.trim()).into_owned());
+                }
+            }
+            Token::Start(tag) if tag.is("link") => {
+                let is_alternate = tag.attr("rel").map_or(false, |rel| {
+                    rel.split_whitespace()
+                        .any(|str| str.eq_ignore_ascii_case("alternate"))
+                });
+                let is_feed = tag.attr("type").map_or(false, |typ| {
+                    FEED_TYPES.iter().any(|str| typ.trim().eq_ignore_ascii_case(str))
+                });
+                let href = tag.attr("href").map(html::decode_entities);
+
+                if let (true, true, Some(href)) = (is_alternate, is_feed, href) {
+                    if let Some(feed_url) = resolve_url(&page.url, &href) {
+                        if links.iter().all(|link| link.feed_url != feed_url) {
+                            let title = tag
+                                .attr("title")
+                                .map(|str| html::decode_entities(str).into_owned());
+                            links.push(FeedLink { feed_url, title });
+                        }
+                    }
+                }
+            }
+            // feed links are only expected in the head
+            Token::End(name) if name.eq_ignore_ascii_case("head") => break,
+            Token::Start(tag) if tag.is("body") => break,
+            _ => {}
+        }
+    }
+
+    for link in &mut links {
+        if link.title.is_none() {
+            link.title = page_title.clone();
+        }
+    }
+    links
+}
+
+async fn probe_common_paths(client: &HttpClient, url: &Uri) -> Option<FeedLink> {
+    for path in COMMON_PATHS {
+        let feed_url = resolve_url(url, path)?;
+        let page = match client.get(&feed_url).await {
+            Ok(page) => page,
+            Err(_) => continue,
+        };
+        if let Ok(feed) = parse_feed(&page) {
+            let title = Some(feed.channel.title.to_owned());
+            return Some(FeedLink { feed_url, title });
+        }
+    }
+    None
+}
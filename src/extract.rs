@@ -0,0 +1,442 @@
+use hyper::Uri;
+use serde::Serialize;
+
+use crate::fetch::{resolve_url, HttpClient};
+use crate::html::{self, Token};
+use crate::result::Result;
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track",
+    "wbr",
+];
+const SKIPPED_ELEMENTS: [&str; 14] = [
+    "script", "style", "noscript", "nav", "aside", "footer", "header", "form", "iframe", "svg", "button",
+    "select", "textarea", "template",
+];
+const ALLOWED_ELEMENTS: [&str; 36] = [
+    "p",
+    "br",
+    "hr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "dl",
+    "dt",
+    "dd",
+    "blockquote",
+    "pre",
+    "code",
+    "a",
+    "img",
+    "em",
+    "strong",
+    "b",
+    "i",
+    "u",
+    "s",
+    "sub",
+    "sup",
+    "figure",
+    "figcaption",
+    "table",
+    "thead",
+    "tbody",
+    "tr",
+    "th",
+    "td",
+];
+const UNLIKELY_NAMES: [&str; 14] = [
+    "comment",
+    "sidebar",
+    "footer",
+    "nav",
+    "menu",
+    "share",
+    "social",
+    "promo",
+    "related",
+    "banner",
+    "cookie",
+    "subscribe",
+    "popup",
+    "advert",
+];
+const LIKELY_NAMES: [&str; 5] = ["article", "content", "main", "body", "post"];
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+// elements nested any deeper are unwrapped, the tree is traversed recursively
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Serialize)]
+pub struct Article {
+    pub url: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub content: String,
+    pub excerpt: String,
+    pub word_count: usize,
+}
+
+// downloads a web page and extracts the main article from it
+pub async fn fetch_article(client: &HttpClient, url: &str) -> Result<Option<Article>> {
+    let page = client.get(url).await?;
+    if !page.is_html() {
+        return Ok(None);
+    }
+    Ok(extract_article(&page.text(), &page.url))
+}
+
+// a simplified version of the readability algorithm, paragraphs contribute to the score of
+// their ancestors and the highest scoring element is assumed to contain the article
+pub fn extract_article(text: &str, url: &Uri) -> Option<Article> {
+    let doc = Node::parse(text);
+    let mut title = None;
+    let mut author = None;
+    doc.visit(&mut |node| {
+        if node.name == "meta" {
+            let key = node
+                .attr("property")
+                .or_else(|| node.attr("name"))
+                .unwrap_or_default();
+            match key {
+                "og:title" => title = node.attr("content").map(str::to_owned),
+                "author" | "article:author" if author.is_none() => {
+                    author = node.attr("content").map(str::to_owned);
+                }
+                _ => {}
+            }
+        } else if node.name == "title" && title.is_none() {
+            title = Some(node.text().trim().to_owned());
+        }
+    });
+
+    let mut best: Option<(&Node, f32)> = None;
+    doc.visit_candidates(&mut |node, score| {
+        if best.map_or(true, |(_, best)| score > best) {
+            best = Some((node, score));
+        }
+    });
+    let (node, _) = best?;
+
+    let mut content = String::new();
+    node.write_clean_html(url, &mut content);
+    let text = node.text();
+    let word_count = text.split_whitespace().count();
+    let excerpt = text.split_whitespace().take(50).collect::<Vec<_>>().join(" ");
+
+    let res = Article {
+        url: url.to_string(),
+        title,
+        author,
+        content,
+        excerpt,
+        word_count,
+    };
+    Some(res)
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Child>,
+    // visible text statistics, computed once the element is closed
+    text_len: usize,
+    link_len: usize,
+    commas: usize,
+}
+
+#[derive(Debug)]
+enum Child {
+    Node(Node),
+    Text(String),
+}
+
+impl Node {
+    // builds a tree out of the tokens, tolerating unclosed and unbalanced tags
+    fn parse(text: &str) -> Node {
+        let mut stack = vec![Node::default()];
+        for token in html::tokenize(text) {
+            match token {
+                Token::Start(tag) => {
+                    let name = tag.name.to_ascii_lowercase();
+                    // paragraphs and list items can't be nested
+                    if name == "p" || name == "li" {
+                        close_element(&mut stack, &name);
+                    }
+                    let attrs = [
+                        "href", "src", "alt", "title", "class", "id", "name", "property", "content",
+                    ]
+                    .into_iter()
+                    .filter_map(|key| {
+                        Some((key.to_owned(), html::decode_entities(tag.attr(key)?).into_owned()))
+                    })
+                    .collect();
+                    let node = Node {
+                        name,
+                        attrs,
+                        ..Node::default()
+                    };
+                    if VOID_ELEMENTS.contains(&node.name.as_str()) {
+                        push_closed(&mut stack, node);
+                    } else if stack.len() <= MAX_DEPTH {
+                        stack.push(node);
+                    }
+                }
+                Token::End(name) => close_element(&mut stack, &name.to_ascii_lowercase()),
+                Token::Text(text) => {
+                    let text = html::decode_entities(text).into_owned();
+                    stack.last_mut().unwrap().children.push(Child::Text(text));
+                }
+            }
+        }
+        while stack.len() > 1 {
+            let node = stack.pop().unwrap();
+            push_closed(&mut stack, node);
+        }
+        stack.pop().unwrap().closed()
+    }
+
+    fn closed(mut self) -> Self {
+        for child in &self.children {
+            match child {
+                Child::Text(text) => {
+                    self.text_len += text.chars().filter(|char| !char.is_whitespace()).count();
+                    self.commas += text.matches(',').count();
+                }
+                Child::Node(node) if !SKIPPED_ELEMENTS.contains(&node.name.as_str()) => {
+                    self.text_len += node.text_len;
+                    self.link_len += node.link_len;
+                    self.commas += node.commas;
+                }
+                Child::Node(_) => {}
+            }
+        }
+        if self.name == "a" {
+            self.link_len = self.text_len;
+        }
+        self
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find_map(|(key, val)| (key == name).then_some(val.as_str()))
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Child::Node(node) => Some(node),
+            Child::Text(_) => None,
+        })
+    }
+
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Node)) {
+        f(self);
+        for node in self.nodes() {
+            node.visit(f);
+        }
+    }
+
+    fn is_unlikely(&self) -> bool {
+        if SKIPPED_ELEMENTS.contains(&self.name.as_str()) {
+            return true;
+        }
+        let names = format!(
+            "{} {}",
+            self.attr("class").unwrap_or_default(),
+            self.attr("id").unwrap_or_default()
+        )
+        .to_lowercase();
+        UNLIKELY_NAMES.iter().any(|str| names.contains(str))
+            && !LIKELY_NAMES.iter().any(|str| names.contains(str))
+    }
+
+    fn text(&self) -> String {
+        let mut res = String::new();
+        self.write_text(&mut res);
+        res
+    }
+
+    fn write_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                Child::Text(text) => out.push_str(text),
+                Child::Node(node) if !SKIPPED_ELEMENTS.contains(&node.name.as_str()) => {
+                    node.write_text(out);
+                    out.push(' ');
+                }
+                Child::Node(_) => {}
+            }
+        }
+    }
+
+    fn link_density(&self) -> f32 {
+        if self.text_len == 0 {
+            1.
+        } else {
+            (self.link_len as f32 / self.text_len as f32).min(1.)
+        }
+    }
+
+    // reports every element that contains scored paragraphs, the score is adjusted for link density
+    fn visit_candidates<'a>(&'a self, f: &mut impl FnMut(&'a Node, f32)) {
+        if self.is_unlikely() {
+            return;
+        }
+        let mut score = 0.;
+        for node in self.nodes().filter(|node| !node.is_unlikely()) {
+            score += node.paragraph_score();
+            // paragraphs contribute half of their score to the grandparent
+            score += node.nodes().map(Node::paragraph_score).sum::<f32>() / 2.;
+        }
+        if score > 0. {
+            f(self, score * (1. - self.link_density()));
+        }
+        for node in self.nodes() {
+            node.visit_candidates(f);
+        }
+    }
+
+    fn paragraph_score(&self) -> f32 {
+        if !matches!(self.name.as_str(), "p" | "pre" | "td" | "blockquote")
+            || self.text_len < MIN_PARAGRAPH_LENGTH
+        {
+            return 0.;
+        }
+        1. + self.commas as f32 + (self.text_len as f32 / 100.).min(3.)
+    }
+
+    // writes the element as HTML limited to a basic set of tags and attributes
+    fn write_clean_html(&self, base: &Uri, out: &mut String) {
+        for child in &self.children {
+            match child {
+                Child::Text(text) => escape_html(text, out),
+                Child::Node(node) if node.is_unlikely() => {}
+                Child::Node(node) if ALLOWED_ELEMENTS.contains(&node.name.as_str()) => {
+                    out.push('<');
+                    out.push_str(&node.name);
+                    for key in ["href", "src", "alt"] {
+                        let val = match node.attr(key) {
+                            Some(val) if key == "alt" => Some(val.to_owned()),
+                            Some(val) => resolve_url(base, val),
+                            None => None,
+                        };
+                        if let Some(val) = val {
+                            out.push_str(&format!(" {key}=\""));
+                            escape_html(&val, out);
+                            out.push('"');
+                        }
+                    }
+                    out.push('>');
+                    if !VOID_ELEMENTS.contains(&node.name.as_str()) {
+                        node.write_clean_html(base, out);
+                        out.push_str(&format!("</{}>", node.name));
+                    }
+                }
+                // other elements are unwrapped
+                Child::Node(node) => node.write_clean_html(base, out),
+            }
+        }
+    }
+}
+
+fn close_element(stack: &mut Vec<Node>, name: &str) {
+    if let Some(pos) = stack.iter().skip(1).rposition(|node| node.name == name) {
+        while stack.len() > pos + 1 {
+            let node = stack.pop().unwrap();
+            push_closed(stack, node);
+        }
+    }
+}
+
+fn push_closed(stack: &mut [Node], node: Node) {
+    stack.last_mut().unwrap().children.push(Child::Node(node.closed()));
+}
+
+fn escape_html(str: &str, out: &mut String) {
+    for char in str.chars() {
+        match char {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = include_str!("../tests/fixtures/article.html");
+
+    fn extract(text: &str) -> Option<Article> {
+        extract_article(text, &Uri::from_static("https://example.com/posts/parsing.html"))
+    }
+
+    fn depth(node: &Node) -> usize {
+        1 + node.nodes().map(depth).max().unwrap_or_default()
+    }
+
+    #[test]
+    fn extracts_the_article() {
+        let article = extract(ARTICLE).unwrap();
+        assert_eq!(article.url, "https://example.com/posts/parsing.html");
+        assert_eq!(article.title.as_deref(), Some("Parsing HTML & other stories"));
+        assert_eq!(article.author.as_deref(), Some("Jane Doe"));
+        assert!(article.content.trim_start().starts_with("<h2>Parsing HTML</h2>"));
+        assert!(article.content.contains("<p>Paragraphs in this article are not closed"));
+        assert!(article.content.contains(r#"<img src="https://example.com/images/tree.png" alt="A tree">"#));
+        assert!(article.content.contains(r#"<a href="https://example.com/posts/guide.html">the guide</a>"#));
+        assert!(article.content.contains("<blockquote>Quotes count as paragraphs too"));
+        for str in ["newsletter", "Archive", "Share", "Copyright", "tracking", "color", "class="] {
+            assert!(!article.content.contains(str), "{str} should have been removed");
+        }
+        assert!(article.excerpt.starts_with("Parsing HTML Browsers are famously lenient"));
+        assert_eq!(article.word_count, 72);
+    }
+
+    #[test]
+    fn closes_unclosed_paragraphs() {
+        let doc = Node::parse("<div><p>first<p>second<li>one<li>two</div>");
+        let div = doc.nodes().next().unwrap();
+        let names = div.nodes().map(|node| node.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["p", "p"]);
+        let items = div.nodes().nth(1).unwrap().nodes().count();
+        assert_eq!(items, 2);
+    }
+
+    #[test]
+    fn computes_text_statistics() {
+        let doc = Node::parse("<p>one, two <a href='/'>three</a><script>four, five</script></p>");
+        let p = doc.nodes().next().unwrap();
+        assert_eq!(p.text_len, "one,two".len() + "three".len());
+        assert_eq!(p.link_len, "three".len());
+        assert_eq!(p.commas, 1);
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let text = format!(
+            "<div><p>{}</p>{}</div>",
+            "text before the nested elements, ".repeat(10),
+            "<div>".repeat(100_000)
+        );
+        let doc = Node::parse(&text);
+        assert_eq!(depth(&doc), MAX_DEPTH + 1);
+        let article = extract(&text).unwrap();
+        assert!(article.content.contains("text before the nested elements"));
+    }
+
+    #[test]
+    fn ignores_pages_without_paragraphs() {
+        assert!(extract("<html><body><div>short</div></body></html>").is_none());
+    }
+}
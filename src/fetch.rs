@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::client::HttpConnector;
+use hyper::header::{CONTENT_TYPE, LOCATION, USER_AGENT};
+use hyper::{Body, Client, Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+
+use crate::result::{Result, ServiceEror};
+
+const MAX_REDIRECTS: usize = 5;
+// limits applied to every request, the client is used with URLs supplied by users
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+pub struct Page {
+    pub url: Uri,
+    pub content_type: Option<String>,
+    pub body: Bytes,
+}
+
+impl Page {
+    pub fn is_html(&self) -> bool {
+        matches!(&self.content_type, Some(str) if str.starts_with("text/html") || str.starts_with("application/xhtml"))
+    }
+
+    pub fn text(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Self {
+            client: Client::builder().build(connector),
+        }
+    }
+}
+
+impl HttpClient {
+    pub async fn get(&self, url: &str) -> Result<Page> {
+        self.fetch(url, MAX_BODY_SIZE).await
+    }
+
+    // fails as soon as the body grows past the limit instead of downloading all of it
+    pub async fn get_with_limit(&self, url: &str, max_size: usize) -> Result<Page> {
+        self.fetch(url, max_size.min(MAX_BODY_SIZE)).await
+    }
+
+    // the whole exchange including redirects and the body has to complete within the timeout
+    async fn fetch(&self, url: &str, max_size: usize) -> Result<Page> {
+        tokio::time::timeout(REQUEST_TIMEOUT, self.follow_redirects(url, max_size))
+            .await
+            .map_err(|_| ServiceEror::Timeout)?
+    }
+
+    async fn follow_redirects(&self, url: &str, max_size: usize) -> Result<Page> {
+        let mut url: Uri = url.parse()?;
+        for _ in 0..MAX_REDIRECTS {
+            if !matches!(url.scheme_str(), Some("http" | "https")) {
+                return Err(ServiceEror::UnsupportedScheme);
+            }
+            let req = Request::get(url.clone())
+                .header(USER_AGENT, concat!("grunt/", env!("CARGO_PKG_VERSION")))
+                .body(Body::empty())?;
+            let resp = self.client.request(req).await?;
+
+            if resp.status().is_redirection() {
+                let location = resp.headers().get(LOCATION).and_then(|val| val.to_str().ok());
+                match location.and_then(|str| resolve_url(&url, str)) {
+                    Some(str) => url = str.parse()?,
+                    None => return Err(ServiceEror::HttpStatus(resp.status())),
+                }
+                continue;
+            }
+            if !resp.status().is_success() {
+                return Err(ServiceEror::HttpStatus(resp.status()));
+            }
+
+            let content_type = resp
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|val| val.to_str().ok())
+                .map(str::to_owned);
+            let body = read_body(resp.into_body(), max_size).await?;
+            return Ok(Page {
+                url,
+                content_type,
+                body,
+            });
+        }
+        Err(ServiceEror::TooManyRedirects)
+    }
+}
+
+// reads the body in chunks, so that a response is rejected as soon as it exceeds the limit
+async fn read_body(mut body: Body, max_size: usize) -> Result<Bytes> {
+    // the lower bound is the Content-Length when the server sends one
+    if body.size_hint().lower() > max_size as u64 {
+        return Err(ServiceEror::ResponseTooLarge(max_size));
+    }
+    let mut buf = vec![];
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buf.len() + chunk.len() > max_size {
+            return Err(ServiceEror::ResponseTooLarge(max_size));
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(buf.into())
+}
+
+// resolves a possibly relative link against the URL of the page it was found on
+pub fn resolve_url(base: &Uri, href: &str) -> Option<String> {
+    let href = href.trim();
+    if href.starts_with("http://") || href.starts_with("https://") {
+        return Some(href.to_owned());
+    }
+    let scheme = base.scheme_str()?;
+    let authority = base.authority()?;
+    if let Some(rest) = href.strip_prefix("//") {
+        return Some(format!("{scheme}://{rest}"));
+    }
+    if href.starts_with('/') {
+        return Some(format!("{scheme}://{authority}{href}"));
+    }
+    let has_scheme = href.split_once(':').map_or(false, |(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if has_scheme {
+        // other schemes like `mailto:` or `data:`
+        return None;
+    }
+    let path = base.path();
+    let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+    Some(format!(
+        "{scheme}://{authority}{}{href}",
+        if dir.is_empty() { "/" } else { dir }
+    ))
+}
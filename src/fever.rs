@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::Arc;
+
+use axum::extract::{Form, Query};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use time::OffsetDateTime;
+
+use crate::auth::find_fever_user;
+use crate::repo::{Repo, Store};
+use crate::result::Result;
+use crate::types::{tag_id, EntryId, FeedId};
+
+const API_VERSION: u32 = 3;
+const MAX_ITEMS: usize = 50;
+// the Fever group that contains all feeds
+const KINDLING_GROUP_ID: u32 = 0;
+
+// a Fever API compatible endpoint, clients authenticate with an `api_key` which
+// is the MD5 hash of `user:password`
+pub fn router() -> Router {
+    Router::new().route("/", get(handle_request).post(handle_request))
+}
+
+async fn handle_request(
+    Extension(store): Extension<Arc<Store>>,
+    Query(query): Query<HashMap<String, String>>,
+    form: Option<Form<HashMap<String, String>>>,
+) -> Result<Json<Map<String, Value>>> {
+    // clients send parameters both in the query and in the form body
+    let mut params = query;
+    if let Some(Form(form)) = form {
+        params.extend(form);
+    }
+
+    let mut res = Map::new();
+    res.insert("api_version".to_owned(), json!(API_VERSION));
+    let user = match params.get("api_key") {
+        Some(key) => find_fever_user(&store, &key.to_lowercase())?,
+        None => None,
+    };
+    let repo = match user {
+        Some(user) => store.get_repo(&user)?,
+        None => None,
+    };
+    let repo = match repo {
+        Some(repo) => repo,
+        None => {
+            res.insert("auth".to_owned(), json!(0));
+            return Ok(Json(res));
+        }
+    };
+    res.insert("auth".to_owned(), json!(1));
+    res.insert(
+        "last_refreshed_on_time".to_owned(),
+        json!(OffsetDateTime::now_utc().unix_timestamp()),
+    );
+
+    if params.contains_key("mark") {
+        mark(&repo, &params, &mut res)?;
+    }
+    if params.contains_key("groups") {
+        let groups = get_groups(&repo)?;
+        let list = groups
+            .keys()
+            .map(|name| json!({ "id": tag_id(name), "title": name }))
+            .collect::<Vec<_>>();
+        res.insert("groups".to_owned(), json!(list));
+        res.insert("feeds_groups".to_owned(), feeds_groups(&groups));
+    }
+    if params.contains_key("feeds") {
+        let mut feeds = vec![];
+        for sub in repo.get_subscriptions()? {
+            let sub = sub.value()?;
+            feeds.push(json!({
+                "id": sub.feed_id,
+                "favicon_id": 0,
+                "title": sub.title,
+                "url": sub.feed_url,
+                "site_url": sub.site_url,
+                "is_spark": 0,
+                "last_updated_on_time": sub.created_at.unix_timestamp(),
+            }));
+        }
+        res.insert("feeds".to_owned(), json!(feeds));
+        res.insert("feeds_groups".to_owned(), feeds_groups(&get_groups(&repo)?));
+    }
+    if params.contains_key("items") {
+        get_items(&repo, &params, &mut res)?;
+    }
+    if params.contains_key("unread_item_ids") {
+        res.insert("unread_item_ids".to_owned(), json!(unread_item_ids(&repo)?));
+    }
+    if params.contains_key("saved_item_ids") {
+        res.insert("saved_item_ids".to_owned(), json!(saved_item_ids(&repo)?));
+    }
+    Ok(Json(res))
+}
+
+// item IDs are entry sequence numbers, they grow with every stored entry so clients can page on them
+fn get_items(repo: &Repo, params: &HashMap<String, String>, res: &mut Map<String, Value>) -> Result<()> {
+    let parse_id = |key: &str| params.get(key).and_then(|str| str.parse::<u64>().ok());
+
+    let entries = if let Some(with_ids) = params.get("with_ids") {
+        let mut entries = vec![];
+        for seq in with_ids
+            .split(',')
+            .filter_map(|str| str.trim().parse().ok())
+            .take(MAX_ITEMS)
+        {
+            if let Some(entry) = repo.get_entry_by_seq(seq)? {
+                entries.push((seq, entry));
+            }
+        }
+        entries
+    } else if let Some(max_id) = parse_id("max_id") {
+        repo.entries_by_seq(..max_id)
+            .rev()
+            .take(MAX_ITEMS)
+            .collect::<Result<_>>()?
+    } else {
+        let since_id = parse_id("since_id").unwrap_or_default();
+        repo.entries_by_seq((Bound::Excluded(since_id), Bound::Unbounded))
+            .take(MAX_ITEMS)
+            .collect::<Result<_>>()?
+    };
+
+    let unread = repo
+        .get_unread()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<HashSet<_>, _>>()?;
+    let starred = repo
+        .get_starred()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let mut items = vec![];
+    for (seq, entry) in entries {
+        let entry = entry.value()?;
+        let id = entry.id;
+        let item = Item {
+            id: seq,
+            feed_id: entry.feed_id,
+            title: entry.title.unwrap_or_default(),
+            author: entry.author.unwrap_or_default(),
+            html: entry.content.or(entry.summary).unwrap_or_default(),
+            url: entry.url.unwrap_or_default(),
+            is_saved: starred.contains(&id).into(),
+            is_read: (!unread.contains(&id)).into(),
+            created_on_time: entry.published.unix_timestamp(),
+        };
+        items.push(json!(item));
+    }
+    res.insert("items".to_owned(), json!(items));
+    res.insert("total_items".to_owned(), json!(repo.count_entries()?));
+    Ok(())
+}
+
+fn mark(repo: &Repo, params: &HashMap<String, String>, res: &mut Map<String, Value>) -> Result<()> {
+    let kind = params.get("mark").map(String::as_str);
+    let action = params.get("as").map(String::as_str);
+    let id = params.get("id").map(String::as_str).unwrap_or_default();
+
+    match (kind, action) {
+        (Some("item"), Some(action)) => {
+            let entry = match id.parse() {
+                Ok(seq) => repo.get_entry_by_seq(seq)?,
+                Err(_) => None,
+            };
+            let id = match entry {
+                Some(entry) => entry.value()?.id,
+                None => return Ok(()),
+            };
+            match action {
+                "read" => repo.delete_unread([id])?,
+                "unread" => repo.add_unread([id]).map(drop)?,
+                "saved" => repo.add_starred([id]).map(drop)?,
+                "unsaved" => repo.delete_starred([id])?,
+                _ => return Ok(()),
+            }
+            if action == "saved" || action == "unsaved" {
+                res.insert("saved_item_ids".to_owned(), json!(saved_item_ids(repo)?));
+                return Ok(());
+            }
+        }
+        (Some(kind @ ("feed" | "group")), Some("read")) => {
+            let before = params
+                .get("before")
+                .and_then(|str| str.parse().ok())
+                .and_then(|ts| OffsetDateTime::from_unix_timestamp(ts).ok())
+                .unwrap_or_else(OffsetDateTime::now_utc);
+            let feeds = if kind == "feed" {
+                match id.parse::<FeedId>() {
+                    Ok(id) => vec![id],
+                    Err(_) => return Ok(()),
+                }
+            } else {
+                let group = id.parse::<u32>().unwrap_or(KINDLING_GROUP_ID);
+                if group == KINDLING_GROUP_ID {
+                    repo.get_subscriptions()?
+                        .iter()
+                        .map(|sub| sub.value().map(|sub| sub.feed_id))
+                        .collect::<Result<_, _>>()?
+                } else {
+                    let groups = get_groups(repo)?;
+                    groups
+                        .into_iter()
+                        .find(|(name, _)| tag_id(name) == group)
+                        .map(|(_, feeds)| feeds)
+                        .unwrap_or_default()
+                }
+            };
+            repo.delete_unread(repo.get_unread_ids_by_feeds(&feeds, before)?)?;
+        }
+        _ => return Ok(()),
+    }
+    res.insert("unread_item_ids".to_owned(), json!(unread_item_ids(repo)?));
+    Ok(())
+}
+
+fn get_groups(repo: &Repo) -> Result<BTreeMap<String, Vec<FeedId>>> {
+    let mut groups: BTreeMap<String, Vec<FeedId>> = BTreeMap::new();
+    for tagging in repo.get_taggings()? {
+        let tagging = tagging.value()?;
+        groups
+            .entry(tagging.name.to_owned())
+            .or_default()
+            .push(tagging.feed_id);
+    }
+    Ok(groups)
+}
+
+fn feeds_groups(groups: &BTreeMap<String, Vec<FeedId>>) -> Value {
+    let res = groups
+        .iter()
+        .map(|(name, feeds)| {
+            let feed_ids = feeds.iter().map(|id| json!(id).to_string()).collect::<Vec<_>>();
+            json!({ "group_id": tag_id(name), "feed_ids": feed_ids.join(",") })
+        })
+        .collect::<Vec<_>>();
+    json!(res)
+}
+
+fn unread_item_ids(repo: &Repo) -> Result<String> {
+    let ids = repo
+        .get_unread()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<Vec<_>, _>>()?;
+    item_ids(repo, ids)
+}
+
+fn saved_item_ids(repo: &Repo) -> Result<String> {
+    let ids = repo
+        .get_starred()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<Vec<_>, _>>()?;
+    item_ids(repo, ids)
+}
+
+fn item_ids(repo: &Repo, ids: Vec<EntryId>) -> Result<String> {
+    let mut res = vec![];
+    for id in ids {
+        if let Some(seq) = repo.get_entry_seq(id)? {
+            res.push(seq.to_string());
+        }
+    }
+    Ok(res.join(","))
+}
+
+#[derive(Debug, Serialize)]
+struct Item<'a> {
+    id: u64,
+    feed_id: FeedId,
+    title: &'a str,
+    author: &'a str,
+    html: &'a str,
+    url: &'a str,
+    is_saved: u8,
+    is_read: u8,
+    created_on_time: i64,
+}
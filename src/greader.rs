@@ -0,0 +1,486 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::Arc;
+
+use axum::extract::{Form, FromRequest, Path, Query, RequestParts};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{async_trait, Extension, Json, Router};
+use serde_json::json;
+use sled_bincode::Value;
+use time::OffsetDateTime;
+
+use crate::auth::{find_reader_user, verify_password};
+use crate::repo::{FeedEntry, Repo, Store};
+use crate::result::Result;
+use crate::types::{Entry, EntryId, FeedId};
+
+const READING_LIST: &str = "user/-/state/com.google/reading-list";
+const READ: &str = "user/-/state/com.google/read";
+const STARRED: &str = "user/-/state/com.google/starred";
+const LABEL_PREFIX: &str = "user/-/label/";
+const FEED_PREFIX: &str = "feed/";
+const ITEM_PREFIX: &str = "tag:google.com,2005:reader/item/";
+const DEFAULT_COUNT: usize = 20;
+const MAX_COUNT: usize = 1000;
+
+// a Google Reader compatible API, clients log in with the account credentials
+// and receive a token they send in the `Authorization: GoogleLogin auth=...` header
+pub fn router() -> Router {
+    let api = Router::new()
+        .route("/token", get(get_token))
+        .route("/user-info", get(get_user_info))
+        .route("/subscription/list", get(get_subscriptions))
+        .route("/tag/list", get(get_tags))
+        .route("/stream/items/ids", get(get_item_ids))
+        .route(
+            "/stream/items/contents",
+            get(get_item_contents).post(get_item_contents),
+        )
+        .route("/stream/contents", get(get_stream_contents))
+        .route("/stream/contents/*stream", get(get_stream_contents))
+        .route("/edit-tag", post(edit_tag))
+        .route("/mark-all-as-read", post(mark_all_as_read));
+
+    Router::new()
+        .route("/accounts/ClientLogin", get(client_login).post(client_login))
+        .nest("/reader/api/0", api)
+}
+
+// parameters are sent in the query and in the form body, some of them are repeated
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find_map(|(k, val)| (k == key).then_some(val.as_str()))
+    }
+
+    fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter_map(move |(k, val)| (k == key).then_some(val.as_str()))
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Params
+where
+    B: axum::body::HttpBody + Send,
+    B::Data: Send,
+    B::Error: Into<axum::BoxError>,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let mut params = req
+            .extract::<Query<Vec<(String, String)>>>()
+            .await
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid query"))?
+            .0;
+        // forms of GET requests are read from the query
+        if req.method() != Method::GET {
+            if let Ok(Form(form)) = req.extract::<Form<Vec<(String, String)>>>().await {
+                params.extend(form);
+            }
+        }
+        Ok(Params(params))
+    }
+}
+
+// checks the login token and makes the repo of the user available to the following extractors
+struct Authorized(String);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Authorized {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let store = req
+            .extensions()
+            .get::<Arc<Store>>()
+            .cloned()
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "missing store"))?;
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|str| str.strip_prefix("GoogleLogin auth="))
+            .unwrap_or_default()
+            .to_owned();
+        let user = match find_reader_user(&store, &token) {
+            Ok(Some(user)) => user,
+            Ok(None) => return Err((StatusCode::UNAUTHORIZED, "Unauthorized")),
+            Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "could not load the user")),
+        };
+        match store.get_repo(&user) {
+            Ok(Some(repo)) => {
+                req.extensions_mut().insert(repo);
+                Ok(Authorized(token))
+            }
+            Ok(None) => Err((StatusCode::UNAUTHORIZED, "Unauthorized")),
+            Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "could not load the user")),
+        }
+    }
+}
+
+async fn client_login(Extension(store): Extension<Arc<Store>>, params: Params) -> Result<Response> {
+    let user = params.get("Email").unwrap_or_default().to_owned();
+    let password = params.get("Passwd").unwrap_or_default().to_owned();
+    let verified = {
+        let (store, user) = (store.clone(), user.clone());
+        tokio::task::spawn_blocking(move || verify_password(&store, &user, &password)).await??
+    };
+    let account = match store.get_account(&user)? {
+        Some(account) if verified => account,
+        _ => return Ok((StatusCode::UNAUTHORIZED, "Error=BadAuthentication\n").into_response()),
+    };
+    let token = account.value()?.reader_token;
+    Ok(format!("SID={token}\nLSID={token}\nAuth={token}\n").into_response())
+}
+
+async fn get_token(Authorized(token): Authorized) -> String {
+    token
+}
+
+async fn get_user_info(_: Authorized, Extension(repo): Extension<Arc<Repo>>) -> Json<serde_json::Value> {
+    Json(json!({
+        "userId": repo.user_name(),
+        "userName": repo.user_name(),
+        "userProfileId": repo.user_name(),
+        "userEmail": "",
+    }))
+}
+
+async fn get_subscriptions(
+    _: Authorized,
+    Extension(repo): Extension<Arc<Repo>>,
+) -> Result<Json<serde_json::Value>> {
+    let tags = get_tags_by_feed(&repo)?;
+    let mut subscriptions = vec![];
+    for sub in repo.get_subscriptions()? {
+        let sub = sub.value()?;
+        let categories = tags
+            .get(&sub.feed_id)
+            .into_iter()
+            .flatten()
+            .map(|name| json!({ "id": label_id(name), "label": name }))
+            .collect::<Vec<_>>();
+        subscriptions.push(json!({
+            "id": feed_stream_id(sub.feed_id),
+            "title": sub.title,
+            "categories": categories,
+            "url": sub.feed_url,
+            "htmlUrl": sub.site_url,
+            "iconUrl": "",
+        }));
+    }
+    Ok(Json(json!({ "subscriptions": subscriptions })))
+}
+
+async fn get_tags(_: Authorized, Extension(repo): Extension<Arc<Repo>>) -> Result<Json<serde_json::Value>> {
+    let mut tags = vec![json!({ "id": STARRED })];
+    for tag in repo.get_tags()? {
+        tags.push(json!({ "id": label_id(&tag.name), "type": "folder", "unread_count": tag.unread_count }));
+    }
+    Ok(Json(json!({ "tags": tags })))
+}
+
+async fn get_item_ids(
+    _: Authorized,
+    Extension(repo): Extension<Arc<Repo>>,
+    params: Params,
+) -> Result<Response> {
+    let stream = match params.get("s") {
+        Some(stream) => stream,
+        None => return Ok((StatusCode::BAD_REQUEST, "missing stream").into_response()),
+    };
+    let (entries, continuation) = query_stream(&repo, stream, &params)?;
+    let entries = entries
+        .iter()
+        .map(|entry| entry.value())
+        .collect::<Result<Vec<_>, _>>()?;
+    let refs = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "id": (entry.id.0 as i64).to_string(),
+                "directStreamIds": [feed_stream_id(entry.feed_id)],
+                "timestampUsec": timestamp_usec(entry.published),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut res = json!({ "itemRefs": refs });
+    if let Some(continuation) = continuation {
+        res["continuation"] = json!(continuation);
+    }
+    Ok(Json(res).into_response())
+}
+
+async fn get_stream_contents(
+    _: Authorized,
+    Extension(repo): Extension<Arc<Repo>>,
+    stream: Option<Path<String>>,
+    params: Params,
+) -> Result<Response> {
+    let stream = match &stream {
+        Some(Path(stream)) => stream.trim_start_matches('/'),
+        None => params.get("s").unwrap_or(READING_LIST),
+    };
+    let (entries, continuation) = query_stream(&repo, stream, &params)?;
+    let entries = entries
+        .iter()
+        .map(|entry| entry.value())
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut res = json!({
+        "id": stream,
+        "updated": OffsetDateTime::now_utc().unix_timestamp(),
+        "items": render_items(&repo, &entries)?,
+    });
+    if let Some(continuation) = continuation {
+        res["continuation"] = json!(continuation);
+    }
+    Ok(Json(res).into_response())
+}
+
+async fn get_item_contents(
+    _: Authorized,
+    Extension(repo): Extension<Arc<Repo>>,
+    params: Params,
+) -> Result<Json<serde_json::Value>> {
+    let mut entries = vec![];
+    for id in params.get_all("i").filter_map(parse_item_id) {
+        if let Some(entry) = repo.get_entry(id)? {
+            entries.push(entry);
+        }
+    }
+    let entries = entries
+        .iter()
+        .map(|entry| entry.value())
+        .collect::<Result<Vec<_>, _>>()?;
+    let res = json!({
+        "id": READING_LIST,
+        "updated": OffsetDateTime::now_utc().unix_timestamp(),
+        "items": render_items(&repo, &entries)?,
+    });
+    Ok(Json(res))
+}
+
+async fn edit_tag(
+    _: Authorized,
+    Extension(repo): Extension<Arc<Repo>>,
+    params: Params,
+) -> Result<&'static str> {
+    let ids = params.get_all("i").filter_map(parse_item_id).collect::<Vec<_>>();
+    for tag in params.get_all("a") {
+        match tag {
+            READ => repo.delete_unread(ids.iter().copied())?,
+            STARRED => repo.add_starred(ids.iter().copied()).map(drop)?,
+            _ => {}
+        }
+    }
+    for tag in params.get_all("r") {
+        match tag {
+            READ => repo.add_unread(ids.iter().copied()).map(drop)?,
+            STARRED => repo.delete_starred(ids.iter().copied())?,
+            _ => {}
+        }
+    }
+    Ok("OK")
+}
+
+async fn mark_all_as_read(
+    _: Authorized,
+    Extension(repo): Extension<Arc<Repo>>,
+    params: Params,
+) -> Result<&'static str> {
+    let stream = params.get("s").unwrap_or(READING_LIST);
+    let before = params
+        .get("ts")
+        .and_then(|str| str.parse::<i128>().ok())
+        .and_then(|usec| OffsetDateTime::from_unix_timestamp_nanos(usec * 1000).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc);
+    let feeds = match get_stream_feeds(&repo, stream)? {
+        Some(feeds) => feeds,
+        None => repo
+            .get_subscriptions()?
+            .iter()
+            .map(|sub| sub.value().map(|sub| sub.feed_id))
+            .collect::<Result<_, _>>()?,
+    };
+    repo.delete_unread(repo.get_unread_ids_by_feeds(&feeds, before)?)?;
+    Ok("OK")
+}
+
+// returns a page of the entries of a stream filtered by the request parameters, newest first by default,
+// entries are visited lazily in the order they were stored in, so the continuation is the sequence
+// number of the last entry on the page
+fn query_stream(
+    repo: &Repo,
+    stream: &str,
+    params: &Params,
+) -> Result<(Vec<Value<FeedEntry>>, Option<String>)> {
+    let unread = repo
+        .get_unread()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<HashSet<_>, _>>()?;
+    let starred = repo
+        .get_starred()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<HashSet<_>, _>>()?;
+    let feeds = get_stream_feeds(repo, stream)?;
+    let exclude_read = params.get_all("xt").any(|str| str == READ);
+    let include_starred = stream == STARRED || params.get_all("it").any(|str| str == STARRED);
+    let only_read = stream == READ;
+    let newer_than = params.get("ot").and_then(|str| str.parse::<i64>().ok());
+    let older_than = params.get("nt").and_then(|str| str.parse::<i64>().ok());
+    let count = params
+        .get("n")
+        .and_then(|str| str.parse().ok())
+        .unwrap_or(DEFAULT_COUNT)
+        .min(MAX_COUNT);
+
+    let oldest_first = params.get("r") == Some("o");
+    let after = match params.get("c").and_then(|str| str.parse::<u64>().ok()) {
+        Some(seq) => Bound::Excluded(seq),
+        None => Bound::Unbounded,
+    };
+    let entries: Box<dyn Iterator<Item = _>> = if oldest_first {
+        Box::new(repo.entries_by_seq((after, Bound::Unbounded)))
+    } else {
+        Box::new(repo.entries_by_seq((Bound::Unbounded, after)).rev())
+    };
+
+    let mut res = vec![];
+    let mut last_seq: Option<u64> = None;
+    for item in entries {
+        let (seq, item) = item?;
+        let entry = item.value()?;
+        let published = entry.published.unix_timestamp();
+        let matches = feeds
+            .as_ref()
+            .map_or(true, |feeds| feeds.contains(&entry.feed_id))
+            && (!include_starred || starred.contains(&entry.id))
+            && (!exclude_read || unread.contains(&entry.id))
+            && (!only_read || !unread.contains(&entry.id))
+            && newer_than.map_or(true, |time| published >= time)
+            && older_than.map_or(true, |time| published <= time);
+        if !matches {
+            continue;
+        }
+        if res.len() == count {
+            let continuation = last_seq.map(|seq| seq.to_string());
+            return Ok((res, continuation));
+        }
+        res.push(item);
+        last_seq = Some(seq);
+    }
+    Ok((res, None))
+}
+
+// returns None for streams that include all feeds
+fn get_stream_feeds(repo: &Repo, stream: &str) -> Result<Option<Vec<FeedId>>> {
+    if let Some(name) = stream.strip_prefix(LABEL_PREFIX) {
+        return Ok(Some(repo.get_feeds_by_tags(&[name.to_owned()])?));
+    }
+    if let Some(id) = stream.strip_prefix(FEED_PREFIX) {
+        return Ok(Some(id.parse::<FeedId>().into_iter().collect()));
+    }
+    Ok(None)
+}
+
+fn render_items(repo: &Repo, entries: &[Entry<'_>]) -> Result<Vec<serde_json::Value>> {
+    let unread = repo
+        .get_unread()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<HashSet<_>, _>>()?;
+    let starred = repo
+        .get_starred()?
+        .iter()
+        .map(|key| key.key())
+        .collect::<Result<HashSet<_>, _>>()?;
+    let tags = get_tags_by_feed(repo)?;
+    let mut feeds = HashMap::new();
+    for sub in repo.get_subscriptions()? {
+        let sub = sub.value()?;
+        let origin = json!({
+            "streamId": feed_stream_id(sub.feed_id),
+            "title": sub.title,
+            "htmlUrl": sub.site_url,
+        });
+        feeds.insert(sub.feed_id, origin);
+    }
+
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let mut categories = vec![READING_LIST.to_owned()];
+            if !unread.contains(&entry.id) {
+                categories.push(READ.to_owned());
+            }
+            if starred.contains(&entry.id) {
+                categories.push(STARRED.to_owned());
+            }
+            categories.extend(
+                tags.get(&entry.feed_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|name| label_id(name)),
+            );
+
+            let url = entry.url.unwrap_or_default();
+            json!({
+                "id": format!("{ITEM_PREFIX}{:016x}", entry.id.0),
+                "crawlTimeMsec": (entry.created_at.unix_timestamp_nanos() / 1_000_000).to_string(),
+                "timestampUsec": timestamp_usec(entry.published),
+                "published": entry.published.unix_timestamp(),
+                "title": entry.title.unwrap_or_default(),
+                "canonical": [{ "href": url }],
+                "alternate": [{ "href": url, "type": "text/html" }],
+                "summary": { "content": entry.content.or(entry.summary).unwrap_or_default() },
+                "author": entry.author.unwrap_or_default(),
+                "categories": categories,
+                "origin": feeds.get(&entry.feed_id).cloned().unwrap_or_else(|| json!({})),
+            })
+        })
+        .collect();
+    Ok(items)
+}
+
+fn get_tags_by_feed(repo: &Repo) -> Result<HashMap<FeedId, BTreeSet<String>>> {
+    let mut res: HashMap<FeedId, BTreeSet<String>> = HashMap::new();
+    for tagging in repo.get_taggings()? {
+        let tagging = tagging.value()?;
+        res.entry(tagging.feed_id)
+            .or_default()
+            .insert(tagging.name.to_owned());
+    }
+    Ok(res)
+}
+
+// items are identified either by the long form with a hex ID or by a signed decimal number
+fn parse_item_id(str: &str) -> Option<EntryId> {
+    match str.strip_prefix(ITEM_PREFIX) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok().map(EntryId),
+        None => str.parse::<i64>().ok().map(|id| EntryId(id as u64)),
+    }
+}
+
+fn feed_stream_id(id: FeedId) -> String {
+    format!("{FEED_PREFIX}{}", id.0)
+}
+
+fn label_id(name: &str) -> String {
+    format!("{LABEL_PREFIX}{name}")
+}
+
+fn timestamp_usec(date: OffsetDateTime) -> String {
+    (date.unix_timestamp_nanos() / 1000).to_string()
+}
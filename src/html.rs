@@ -0,0 +1,314 @@
+use std::borrow::Cow;
+
+// a minimal, lenient HTML tokenizer, it only understands as much as is needed
+// to pick out tags and text from real-world pages
+pub fn tokenize(html: &str) -> Tokenizer<'_> {
+    Tokenizer {
+        rest: html,
+        raw_text: None,
+    }
+}
+
+#[derive(Debug)]
+pub enum Token<'a> {
+    Start(Tag<'a>),
+    End(&'a str),
+    Text(&'a str),
+}
+
+#[derive(Debug)]
+pub struct Tag<'a> {
+    pub name: &'a str,
+    attrs: &'a str,
+}
+
+impl<'a> Tag<'a> {
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&'a str> {
+        Attrs(self.attrs).find_map(|(key, val)| key.eq_ignore_ascii_case(name).then_some(val))
+    }
+}
+
+pub struct Tokenizer<'a> {
+    rest: &'a str,
+    // the contents of script and style elements are not markup
+    raw_text: Option<&'static str>,
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            if let Some(name) = self.raw_text.take() {
+                let end = find_closing_tag(self.rest, name).unwrap_or(self.rest.len());
+                let (text, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                if !text.is_empty() {
+                    return Some(Token::Text(text));
+                }
+                continue;
+            }
+            if !self.rest.starts_with('<') {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let (text, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                return Some(Token::Text(text));
+            }
+            if let Some(rest) = self.rest.strip_prefix("<!--") {
+                self.rest = rest.find("-->").map_or("", |i| &rest[i + 3..]);
+                continue;
+            }
+            if self.rest.starts_with("<!") || self.rest.starts_with("<?") {
+                self.rest = self.rest.find('>').map_or("", |i| &self.rest[i + 1..]);
+                continue;
+            }
+
+            if !self.rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+                self.rest = &self.rest[1..];
+                return Some(Token::Text("<"));
+            }
+
+            let end = self.rest.find('>').unwrap_or(self.rest.len());
+            let inner = &self.rest[1..end];
+            self.rest = self.rest.get(end + 1..).unwrap_or("");
+
+            if let Some(name) = inner.strip_prefix('/') {
+                return Some(Token::End(name.trim()));
+            }
+            let (inner, self_closing) = match inner.strip_suffix('/') {
+                Some(inner) => (inner, true),
+                None => (inner, false),
+            };
+            let name_end = inner
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(inner.len());
+            let (name, attrs) = inner.split_at(name_end);
+            let tag = Tag { name, attrs };
+            if !self_closing {
+                self.raw_text = ["script", "style"].into_iter().find(|raw| tag.is(raw));
+            }
+            return Some(Token::Start(tag));
+        }
+    }
+}
+
+struct Attrs<'a>(&'a str);
+
+impl<'a> Iterator for Attrs<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.0.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(name_end);
+        let rest = rest.trim_start();
+
+        let rest = match rest.strip_prefix('=') {
+            Some(rest) => rest.trim_start(),
+            None => {
+                self.0 = rest;
+                return Some((name, ""));
+            }
+        };
+        let (val, rest) = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let rest = &rest[1..];
+                let end = rest.find(quote).unwrap_or(rest.len());
+                (&rest[..end], rest.get(end + 1..).unwrap_or(""))
+            }
+            _ => {
+                let end = rest.find(|c: char| c.is_ascii_whitespace()).unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        self.0 = rest;
+        Some((name, val))
+    }
+}
+
+fn find_closing_tag(str: &str, name: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(i) = str[offset..].find("</") {
+        let start = offset + i;
+        match str.get(start + 2..start + 2 + name.len()) {
+            Some(tag) if tag.eq_ignore_ascii_case(name) => return Some(start),
+            _ => {}
+        }
+        offset = start + 2;
+    }
+    None
+}
+
+// returns the text content of an HTML fragment
+pub fn strip_tags(html: &str) -> String {
+    let mut res = String::with_capacity(html.len());
+    let mut skip = false;
+    for token in tokenize(html) {
+        match token {
+            Token::Start(tag) => {
+                skip = tag.is("script") || tag.is("style");
+                res.push(' ');
+            }
+            Token::End(_) => {
+                skip = false;
+                res.push(' ');
+            }
+            Token::Text(text) if !skip => res.push_str(&decode_entities(text)),
+            Token::Text(_) => {}
+        }
+    }
+    res
+}
+
+pub fn decode_entities(str: &str) -> Cow<'_, str> {
+    if !str.contains('&') {
+        return Cow::Borrowed(str);
+    }
+
+    let mut res = String::with_capacity(str.len());
+    let mut rest = str;
+    while let Some(i) = rest.find('&') {
+        res.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let char = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((char, end))
+        });
+        match decoded {
+            Some((char, end)) => {
+                res.push(char);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    Cow::Owned(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn describe(html: &str) -> Vec<String> {
+        tokenize(html)
+            .map(|token| match token {
+                Token::Start(tag) => format!("<{}>", tag.name),
+                Token::End(name) => format!("</{name}>"),
+                Token::Text(text) => text.to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tokenizes_tags_and_text() {
+        assert_eq!(
+            describe("<p class=a>Hello <b>world</b></p>"),
+            ["<p>", "Hello ", "<b>", "world", "</b>", "</p>"]
+        );
+        assert_eq!(describe("<br/>text<img src=x />"), ["<br>", "text", "<img>"]);
+    }
+
+    #[test]
+    fn skips_comments_and_declarations() {
+        assert_eq!(
+            describe("<!DOCTYPE html><!-- <p>hidden</p> --><?xml version=\"1.0\"?>text"),
+            ["text"]
+        );
+        assert_eq!(describe("before<!-- unterminated"), ["before"]);
+    }
+
+    #[test]
+    fn treats_script_and_style_contents_as_text() {
+        assert_eq!(
+            describe("<script>if (a < b) { x = '</p>'; }</script><p>"),
+            ["<script>", "if (a < b) { x = '</p>'; }", "</script>", "<p>"]
+        );
+        assert_eq!(
+            describe("<style>a > b {}</STYLE>after"),
+            ["<style>", "a > b {}", "</STYLE>", "after"]
+        );
+    }
+
+    #[test]
+    fn keeps_stray_angle_brackets_as_text() {
+        assert_eq!(describe("1 < 2"), ["1 ", "<", " 2"]);
+        assert_eq!(describe("<p"), ["<p>"]);
+    }
+
+    #[test]
+    fn parses_attributes() {
+        let tag = match tokenize(r#"<a HREF="/x?a=1&amp;b=2" data-x='single' checked title=bare>"#).next() {
+            Some(Token::Start(tag)) => tag,
+            token => panic!("unexpected token {token:?}"),
+        };
+        assert!(tag.is("A"));
+        assert_eq!(tag.attr("href"), Some("/x?a=1&amp;b=2"));
+        assert_eq!(tag.attr("data-x"), Some("single"));
+        assert_eq!(tag.attr("checked"), Some(""));
+        assert_eq!(tag.attr("title"), Some("bare"));
+        assert_eq!(tag.attr("missing"), None);
+    }
+
+    #[test]
+    fn handles_unterminated_attribute_quotes() {
+        let tag = match tokenize(r#"<a href="/x>"#).next() {
+            Some(Token::Start(tag)) => tag,
+            token => panic!("unexpected token {token:?}"),
+        };
+        assert_eq!(tag.attr("href"), Some("/x"));
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(decode_entities("a &amp; b &lt;c&gt; &quot;d&quot; &apos;"), "a & b <c> \"d\" '");
+        assert_eq!(decode_entities("&#65;&#x42;&#X43;&nbsp;"), "ABC\u{a0}");
+        assert!(matches!(decode_entities("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn keeps_invalid_entities() {
+        assert_eq!(decode_entities("AT&T"), "AT&T");
+        assert_eq!(decode_entities("&unknown;"), "&unknown;");
+        assert_eq!(decode_entities("&#xZZ; &#1114112;"), "&#xZZ; &#1114112;");
+        assert_eq!(decode_entities("&amp"), "&amp");
+        assert_eq!(decode_entities("& a very long text;"), "& a very long text;");
+        assert_eq!(decode_entities("&"), "&");
+    }
+
+    #[test]
+    fn strips_tags() {
+        let text = strip_tags("<p>Fish &amp; chips</p><script>alert(1)</script><p>done</p>");
+        assert_eq!(text.split_whitespace().collect::<Vec<_>>(), ["Fish", "&", "chips", "done"]);
+    }
+}
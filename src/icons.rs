@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use futures_util::future::join_all;
+use hyper::body::Bytes;
+use hyper::Uri;
+
+use crate::fetch::{resolve_url, HttpClient};
+use crate::html::{self, Token};
+use crate::repo::Store;
+use crate::result::Result;
+use crate::types::Icon;
+
+const MAX_ICON_SIZE: usize = 256 * 1024;
+
+// fetches favicons for the sites of subscriptions that don't have one yet
+pub async fn refresh_icons(store: &Arc<Store>) -> Result<()> {
+    let mut hosts = vec![];
+    for res in store.get_subscriptions()? {
+        let sub = res.value()?;
+        let url = match sub.site_url.parse::<Uri>() {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        if let Some(host) = url.host() {
+            if !hosts.iter().any(|(str, _)| str == host) && store.get_icon(host)?.is_none() {
+                hosts.push((host.to_owned(), url));
+            }
+        }
+    }
+
+    let client = HttpClient::default();
+    let tasks = hosts.iter().map(|(host, url)| {
+        let client = &client;
+        async move { (host, fetch_icon(client, url).await) }
+    });
+    for (host, res) in join_all(tasks).await {
+        match res {
+            Ok(Some((content_type, data))) => store.add_icon(host, &Icon::new(&content_type, &data))?,
+            Ok(None) => tracing::info!("no icon found for {host}"),
+            Err(err) => tracing::info!("failed to retrieve an icon for {host}: {err}"),
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_icon(client: &HttpClient, site_url: &Uri) -> Result<Option<(String, Bytes)>> {
+    let page = client.get(&site_url.to_string()).await?;
+    let mut candidates = if page.is_html() {
+        find_icon_links(&page.text(), &page.url)
+    } else {
+        vec![]
+    };
+    candidates.extend(resolve_url(&page.url, "/favicon.ico"));
+
+    for url in candidates {
+        let icon = match client.get_with_limit(&url, MAX_ICON_SIZE).await {
+            Ok(icon) => icon,
+            Err(_) => continue,
+        };
+        let content_type = match icon.content_type.as_deref() {
+            Some(str) if str.starts_with("image/") => str.to_owned(),
+            // favicon.ico is often served without a proper content type
+            _ if url.ends_with(".ico") => "image/x-icon".to_owned(),
+            _ => continue,
+        };
+        if !icon.body.is_empty() {
+            return Ok(Some((content_type, icon.body)));
+        }
+    }
+    Ok(None)
+}
+
+fn find_icon_links(text: &str, base: &Uri) -> Vec<String> {
+    let mut links = vec![];
+    for token in html::tokenize(text) {
+        match token {
+            Token::Start(tag) if tag.is("link") => {
+                let is_icon = tag.attr("rel").map_or(false, |rel| {
+                    rel.split_whitespace().any(|str| {
+                        str.eq_ignore_ascii_case("icon") || str.eq_ignore_ascii_case("apple-touch-icon")
+                    })
+                });
+                if is_icon {
+                    let href = tag.attr("href").map(html::decode_entities);
+                    links.extend(href.and_then(|href| resolve_url(base, &href)));
+                }
+            }
+            Token::End(name) if name.eq_ignore_ascii_case("head") => break,
+            Token::Start(tag) if tag.is("body") => break,
+            _ => {}
+        }
+    }
+    links
+}
@@ -1,15 +1,32 @@
+use std::error::Error;
+use std::io::IsTerminal;
 use std::sync::Arc;
 use std::time::Duration;
 
+use auth::Password;
 use futures_util::future::join;
 use gumdrop::Options;
+use icons::refresh_icons;
 use refresh::refresh_all_feeds;
-use repo::Repo;
+use repo::Store;
 
+mod atom;
+mod auth;
 mod codecs;
+mod digest;
+mod discovery;
+mod extract;
+mod fetch;
+mod fever;
+mod greader;
+mod html;
+mod icons;
+mod miniflux;
+mod nextcloud;
 mod refresh;
 mod repo;
 mod result;
+mod search;
 mod service;
 mod types;
 
@@ -21,12 +38,67 @@ pub struct AppConfig {
     port: u16,
     #[options(help = "directory to store the database in", default = "db")]
     db_path: String,
-    #[options(help = "basic auth password", required)]
-    user: String,
-    #[options(help = "basic auth user name", required)]
-    password: String,
+    #[options(help = "user to create or update on startup (env: GRUNT_USER)")]
+    user: Option<String>,
+    #[options(
+        no_short,
+        help = "file to read the password of the user created on startup from"
+    )]
+    password_file: Option<String>,
+    #[options(
+        no_short,
+        help = "Argon2 hash of the password of the user created on startup (env: GRUNT_PASSWORD_HASH)"
+    )]
+    password_hash: Option<String>,
+    #[options(
+        no_short,
+        help = "file to read the admin password of the user created on startup from (env: GRUNT_ADMIN_PASSWORD)"
+    )]
+    admin_password_file: Option<String>,
     #[options(help = "refresh time interval in minutes", default = "30")]
     interval_minutes: u32,
+    #[options(command)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Options)]
+enum Command {
+    #[options(help = "add a user, the password is read from stdin")]
+    AddUser(UserOptions),
+    #[options(help = "list all users")]
+    ListUsers(NoOptions),
+    #[options(help = "reset the password of a user, the password is read from stdin")]
+    ResetPassword(UserOptions),
+    #[options(help = "disable a user")]
+    DisableUser(UserOptions),
+    #[options(help = "enable a disabled user")]
+    EnableUser(UserOptions),
+    #[options(help = "delete a user along with their data")]
+    DeleteUser(UserOptions),
+    #[options(help = "set the admin password of a user, the password is read from stdin")]
+    SetAdminPassword(UserOptions),
+    #[options(help = "remove the admin password of a user")]
+    RemoveAdminPassword(UserOptions),
+    #[options(help = "set the Fever API password of a user, the password is read from stdin")]
+    SetFeverPassword(UserOptions),
+    #[options(help = "remove the Fever API password of a user")]
+    RemoveFeverPassword(UserOptions),
+    #[options(help = "print the Argon2 hash of a password read from stdin")]
+    HashPassword(NoOptions),
+}
+
+#[derive(Debug, Clone, Options)]
+struct UserOptions {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, required, help = "user name")]
+    name: String,
+}
+
+#[derive(Debug, Clone, Options)]
+struct NoOptions {
+    #[options(help = "print help message")]
+    help: bool,
 }
 
 #[tokio::main]
@@ -34,20 +106,168 @@ async fn main() {
     tracing_subscriber::fmt::init();
     let opts = AppConfig::parse_args_default_or_exit();
 
-    let repo = Arc::new(Repo::new(&opts.db_path).unwrap());
-    let daemon = tokio::spawn(refresh_daemon(repo.clone(), opts.interval_minutes.into()));
-    let service = service::run(repo, &opts);
+    let store = Arc::new(Store::new(&opts.db_path).unwrap());
+    if let Some(command) = opts.command.clone() {
+        if let Err(err) = run_command(&store, command) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(err) = setup_startup_user(&store, &opts) {
+        eprintln!("failed to set up the startup user: {err}");
+        std::process::exit(2);
+    }
+    if store.get_accounts().unwrap().is_empty() {
+        tracing::warn!("there are no users, add one with the add-user command");
+    }
+
+    let daemon = tokio::spawn(refresh_daemon(store.clone(), opts.interval_minutes.into()));
+    let service = service::run(store, &opts);
 
     join(daemon, service).await.0.unwrap();
 }
 
-async fn refresh_daemon(repo: Arc<Repo>, interval: u64) {
+fn run_command(store: &Store, command: Command) -> Result<(), Box<dyn Error>> {
+    let not_found = |name: &str| -> Box<dyn Error> { format!("user {name} does not exist").into() };
+    match command {
+        Command::AddUser(opts) => {
+            if !auth::create_user(store, &opts.name, &read_password()?)? {
+                return Err(format!("user {} already exists", opts.name).into());
+            }
+        }
+        Command::ListUsers(_) => {
+            for res in store.get_accounts()? {
+                let account = res.value()?;
+                let admin = store.get_admin_password(account.name)?.is_some();
+                let status = match (account.disabled, admin) {
+                    (true, _) => " (disabled)",
+                    (false, true) => " (admin)",
+                    (false, false) => "",
+                };
+                println!("{}{status}", account.name);
+            }
+        }
+        Command::ResetPassword(opts) => {
+            if !auth::set_password(store, &opts.name, &read_password()?)? {
+                return Err(not_found(&opts.name));
+            }
+        }
+        Command::DisableUser(opts) => {
+            if !auth::set_disabled(store, &opts.name, true)? {
+                return Err(not_found(&opts.name));
+            }
+        }
+        Command::EnableUser(opts) => {
+            if !auth::set_disabled(store, &opts.name, false)? {
+                return Err(not_found(&opts.name));
+            }
+        }
+        Command::DeleteUser(opts) => {
+            if !store.delete_user(&opts.name)? {
+                return Err(not_found(&opts.name));
+            }
+        }
+        Command::SetAdminPassword(opts) => {
+            if !auth::set_admin_password(store, &opts.name, &read_password()?)? {
+                return Err(not_found(&opts.name));
+            }
+        }
+        Command::RemoveAdminPassword(opts) => {
+            if !store.delete_admin_password(&opts.name)? {
+                return Err(format!("user {} has no admin password", opts.name).into());
+            }
+        }
+        Command::SetFeverPassword(opts) => {
+            if !auth::set_fever_password(store, &opts.name, &read_password()?)? {
+                return Err(not_found(&opts.name));
+            }
+        }
+        Command::RemoveFeverPassword(opts) => {
+            if !auth::remove_fever_password(store, &opts.name)? {
+                return Err(format!("user {} has no Fever password", opts.name).into());
+            }
+        }
+        Command::HashPassword(_) => println!("{}", auth::hash_password(&read_password()?)?),
+    }
+    Ok(())
+}
+
+fn setup_startup_user(store: &Store, opts: &AppConfig) -> Result<(), Box<dyn Error>> {
+    let admin_password = match &opts.admin_password_file {
+        Some(path) => Some(read_password_file(path)?),
+        None => std::env::var("GRUNT_ADMIN_PASSWORD").ok(),
+    };
+    match (startup_user(opts)?, admin_password) {
+        (_, Some(password)) if password.is_empty() => Err("the admin password can't be empty".into()),
+        (Some((user, password)), admin_password) => {
+            auth::ensure_user(store, &user, &password)?;
+            if let Some(admin_password) = admin_password {
+                auth::ensure_admin_password(store, &user, &admin_password)?;
+            }
+            Ok(())
+        }
+        (None, Some(_)) => Err("an admin password has to be provided along with a user".into()),
+        (None, None) => Ok(()),
+    }
+}
+
+// the startup user can be configured with environment variables or a password file,
+// so that the password doesn't have to be passed as an argument
+fn startup_user(opts: &AppConfig) -> Result<Option<(String, Password)>, Box<dyn Error>> {
+    let password = if let Some(path) = &opts.password_file {
+        Some(Password::Plain(read_password_file(path)?))
+    } else if let Ok(password) = std::env::var("GRUNT_PASSWORD") {
+        Some(Password::Plain(password))
+    } else {
+        option_or_env(&opts.password_hash, "GRUNT_PASSWORD_HASH").map(Password::Hashed)
+    };
+    match (option_or_env(&opts.user, "GRUNT_USER"), password) {
+        (Some(_), Some(Password::Plain(password) | Password::Hashed(password))) if password.is_empty() => {
+            Err("the password can't be empty".into())
+        }
+        (Some(user), Some(password)) => Ok(Some((user, password))),
+        (None, None) => Ok(None),
+        _ => Err("a user and a password have to be provided together".into()),
+    }
+}
+
+fn read_password_file(path: &str) -> Result<String, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(contents.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+fn option_or_env(opt: &Option<String>, var: &str) -> Option<String> {
+    opt.clone().or_else(|| std::env::var(var).ok())
+}
+
+// passwords are not accepted as arguments, so that they don't end up in the shell history,
+// they're prompted for without echo on a terminal and otherwise read from piped stdin
+fn read_password() -> Result<String, Box<dyn Error>> {
+    let password = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password("password: ")?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_owned()
+    };
+    if password.is_empty() {
+        return Err("the password can't be empty".into());
+    }
+    Ok(password)
+}
+
+async fn refresh_daemon(store: Arc<Store>, interval: u64) {
     let mut interval = tokio::time::interval(Duration::from_secs(interval * 60));
 
     loop {
         interval.tick().await;
-        if let Err(err) = refresh_all_feeds(&repo).await {
+        if let Err(err) = refresh_all_feeds(&store).await {
             tracing::error!("subscription refresh failed: {err}");
         }
+        if let Err(err) = refresh_icons(&store).await {
+            tracing::error!("icon refresh failed: {err}");
+        }
     }
 }
@@ -0,0 +1,743 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sled_bincode::Value;
+use time::OffsetDateTime;
+
+use crate::digest::md5_hex;
+use crate::refresh::{add_feed, refresh_subscription};
+use crate::repo::{FeedEntry, Repo};
+use crate::result::Result;
+use crate::types::{tag_id, Entry, EntryId, FeedId, FeedOptions, Subscription, Tagging, TokenScope};
+use crate::{codecs, html};
+
+const DEFAULT_LIMIT: usize = 100;
+// feeds without tags belong to this category, it can't be modified
+const DEFAULT_CATEGORY_ID: u32 = 0;
+const DEFAULT_CATEGORY_TITLE: &str = "All";
+const WORDS_PER_MINUTE: usize = 265;
+
+// a Miniflux compatible API, clients are configured with `[grunt-host]/miniflux`
+// and authenticate with the BasicAuth credentials, tags are exposed as categories
+pub fn router() -> Router {
+    let api = Router::new()
+        .route("/me", get(get_me))
+        .route("/feeds", get(get_feeds).post(create_feed))
+        .route("/feeds/:id", get(get_feed).put(update_feed).delete(delete_feed))
+        .route("/feeds/:id/refresh", put(refresh_feed))
+        .route("/feeds/:id/entries", get(get_feed_entries))
+        .route("/feeds/:id/mark-all-as-read", put(mark_feed_read))
+        .route("/categories", get(get_categories).post(create_category))
+        .route("/categories/:id", put(rename_category).delete(delete_category))
+        .route("/categories/:id/feeds", get(get_category_feeds))
+        .route("/categories/:id/entries", get(get_category_entries))
+        .route("/categories/:id/mark-all-as-read", put(mark_category_read))
+        .route("/entries", get(get_entries).put(update_entries))
+        .route("/entries/:id", get(get_entry))
+        .route("/entries/:id/bookmark", put(toggle_bookmark));
+
+    Router::new().nest("/v1", api)
+}
+
+async fn get_me(
+    Extension(repo): Extension<Arc<Repo>>,
+    Extension(scope): Extension<TokenScope>,
+) -> Json<serde_json::Value> {
+    Json(json!({
+        "id": repo.user_id(),
+        "username": repo.user_name(),
+        "is_admin": scope == TokenScope::Admin,
+        "theme": "light_serif",
+        "language": "en_US",
+        "timezone": "UTC",
+        "entry_sorting_direction": "desc",
+    }))
+}
+
+async fn get_feeds(Extension(repo): Extension<Arc<Repo>>) -> Result<Response> {
+    render_feeds(&repo, |_| true)
+}
+
+async fn get_feed(Extension(repo): Extension<Arc<Repo>>, Path(feed_id): Path<FeedId>) -> Result<Response> {
+    let sub = match repo.get_subscription(feed_id)? {
+        Some(sub) => sub,
+        None => return Ok(not_found()),
+    };
+    let tags = get_tags_by_feed(&repo)?;
+    let sub = sub.value()?;
+    let feed = Feed::new(repo.user_id(), &sub, tags.get(&feed_id), repo.get_feed_options(feed_id)?);
+    Ok(Json(feed).into_response())
+}
+
+async fn create_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(add_feed_req): Json<AddFeed>,
+) -> Result<Response> {
+    if repo.get_subscription_by_url(&add_feed_req.feed_url)?.is_some() {
+        return Ok(error(StatusCode::CONFLICT, "This feed already exists."));
+    }
+    let category = match add_feed_req.category_id {
+        Some(id) => match find_category(&repo, id)? {
+            Some(category) => category,
+            None => return Ok(error(StatusCode::BAD_REQUEST, "This category does not exist.")),
+        },
+        None => None,
+    };
+    let feed_id = match add_feed(&repo, &add_feed_req.feed_url).await? {
+        Some(id) => id,
+        None => return Ok(error(StatusCode::BAD_REQUEST, "Unable to read this feed.")),
+    };
+    if let Some(name) = category {
+        repo.add_tagging(&Tagging::new(repo.new_tagging_id()?, feed_id, &name))?;
+    }
+    if add_feed_req.crawler {
+        repo.set_feed_options(feed_id, &FeedOptions {
+            extract_content: true,
+        })?;
+    }
+    Ok((StatusCode::CREATED, Json(json!({ "feed_id": feed_id }))).into_response())
+}
+
+async fn update_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+    Json(update): Json<UpdateFeed>,
+) -> Result<Response> {
+    if repo.get_subscription(feed_id)?.is_none() {
+        return Ok(not_found());
+    }
+    if let Some(category_id) = update.category_id {
+        let category = match find_category(&repo, category_id)? {
+            Some(category) => category,
+            None => return Ok(error(StatusCode::BAD_REQUEST, "This category does not exist.")),
+        };
+        // a feed can only be in a single category
+        for tagging in repo.get_taggings()? {
+            let tagging = tagging.value()?;
+            if tagging.feed_id == feed_id {
+                repo.delete_tagging(tagging.id)?;
+            }
+        }
+        if let Some(name) = category {
+            repo.add_tagging(&Tagging::new(repo.new_tagging_id()?, feed_id, &name))?;
+        }
+    }
+    if let Some(title) = &update.title {
+        repo.rename_subscription(feed_id, title)?;
+    }
+    if let Some(crawler) = update.crawler {
+        repo.set_feed_options(feed_id, &FeedOptions {
+            extract_content: crawler,
+        })?;
+    }
+    get_feed(Extension(repo), Path(feed_id)).await
+}
+
+async fn delete_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+) -> Result<Response> {
+    if repo.get_subscription(feed_id)?.is_none() {
+        return Ok(not_found());
+    }
+    repo.delete_subscription(feed_id)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn refresh_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+) -> Result<Response> {
+    let sub = match repo.get_subscription(feed_id)? {
+        Some(sub) => sub,
+        None => return Ok(not_found()),
+    };
+    let feed_url = sub.value()?.feed_url.to_owned();
+    refresh_subscription(&repo, feed_id, &feed_url).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn get_feed_entries(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response> {
+    let mut filter = EntryFilter::new(&params);
+    filter.feeds = Some(vec![feed_id]);
+    render_entries(&repo, &filter)
+}
+
+async fn mark_feed_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+) -> Result<Response> {
+    repo.delete_unread(repo.get_unread_ids_by_feeds(&[feed_id], OffsetDateTime::now_utc())?)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn get_categories(Extension(repo): Extension<Arc<Repo>>) -> Result<Json<Vec<Category>>> {
+    let mut categories = vec![Category::default_category(repo.user_id())];
+    categories.extend(repo.get_folders()?.into_iter().map(|title| Category::new(repo.user_id(), title)));
+    Ok(Json(categories))
+}
+
+async fn create_category(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(add_category): Json<AddCategory>,
+) -> Result<Response> {
+    if add_category.title.is_empty() {
+        return Ok(error(StatusCode::BAD_REQUEST, "The title is mandatory."));
+    }
+    if repo.get_folders()?.contains(&add_category.title) {
+        return Ok(error(StatusCode::CONFLICT, "This category already exists."));
+    }
+    repo.add_folder(&add_category.title)?;
+    Ok((StatusCode::CREATED, Json(Category::new(repo.user_id(), add_category.title))).into_response())
+}
+
+async fn rename_category(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(category_id): Path<u32>,
+    Json(rename): Json<AddCategory>,
+) -> Result<Response> {
+    let name = match find_category(&repo, category_id)? {
+        Some(Some(name)) => name,
+        Some(None) => return Ok(error(StatusCode::BAD_REQUEST, "This category can't be modified.")),
+        None => return Ok(not_found()),
+    };
+    if rename.title.is_empty() {
+        return Ok(error(StatusCode::BAD_REQUEST, "The title is mandatory."));
+    }
+    if repo.get_folders()?.contains(&rename.title) {
+        return Ok(error(StatusCode::CONFLICT, "This category already exists."));
+    }
+    repo.rename_tag(&name, &rename.title)?;
+    Ok((StatusCode::CREATED, Json(Category::new(repo.user_id(), rename.title))).into_response())
+}
+
+// feeds in the category are moved to the default one
+async fn delete_category(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(category_id): Path<u32>,
+) -> Result<Response> {
+    match find_category(&repo, category_id)? {
+        Some(Some(name)) => {
+            repo.delete_tag(&name)?;
+            Ok(StatusCode::NO_CONTENT.into_response())
+        }
+        Some(None) => Ok(error(StatusCode::BAD_REQUEST, "This category can't be removed.")),
+        None => Ok(not_found()),
+    }
+}
+
+async fn get_category_feeds(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(category_id): Path<u32>,
+) -> Result<Response> {
+    if find_category(&repo, category_id)?.is_none() {
+        return Ok(not_found());
+    }
+    render_feeds(&repo, |tags| category_of(tags).0 == category_id)
+}
+
+async fn get_category_entries(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(category_id): Path<u32>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response> {
+    let mut filter = EntryFilter::new(&params);
+    filter.feeds = Some(get_category_feed_ids(&repo, category_id)?);
+    render_entries(&repo, &filter)
+}
+
+async fn mark_category_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(category_id): Path<u32>,
+) -> Result<Response> {
+    let feeds = get_category_feed_ids(&repo, category_id)?;
+    repo.delete_unread(repo.get_unread_ids_by_feeds(&feeds, OffsetDateTime::now_utc())?)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn get_entries(
+    Extension(repo): Extension<Arc<Repo>>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response> {
+    let mut filter = EntryFilter::new(&params);
+    let param = |key| {
+        params
+            .iter()
+            .find_map(|(k, val)| (k == key).then_some(val.as_str()))
+    };
+    if let Some(feed_id) = param("feed_id").and_then(|str| str.parse().ok()) {
+        filter.feeds = Some(vec![feed_id]);
+    } else if let Some(category_id) = param("category_id").and_then(|str| str.parse().ok()) {
+        filter.feeds = Some(get_category_feed_ids(&repo, category_id)?);
+    }
+    render_entries(&repo, &filter)
+}
+
+async fn get_entry(Extension(repo): Extension<Arc<Repo>>, Path(entry_id): Path<u64>) -> Result<Response> {
+    let entry = match repo.get_entry_by_seq(entry_id)? {
+        Some(entry) => entry,
+        None => return Ok(not_found()),
+    };
+    let context = Context::new(&repo)?;
+    let entry = entry.value()?;
+    Ok(Json(render_entry(&repo, &context, entry_id, &entry)?).into_response())
+}
+
+async fn update_entries(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(update): Json<UpdateEntries>,
+) -> Result<Response> {
+    let ids = get_entry_ids(&repo, update.entry_ids)?;
+    match update.status.as_str() {
+        "read" => repo.delete_unread(ids)?,
+        "unread" => repo.add_unread(ids).map(drop)?,
+        _ => return Ok(error(StatusCode::BAD_REQUEST, "Invalid entry status.")),
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn toggle_bookmark(Extension(repo): Extension<Arc<Repo>>, Path(entry_id): Path<u64>) -> Result<Response> {
+    let entry_id = match repo.get_entry_by_seq(entry_id)? {
+        Some(entry) => entry.value()?.id,
+        None => return Ok(not_found()),
+    };
+    let starred = repo
+        .get_starred()?
+        .iter()
+        .any(|key| matches!(key.key(), Ok(id) if id == entry_id));
+    if starred {
+        repo.delete_starred([entry_id])?;
+    } else {
+        repo.add_starred([entry_id])?;
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn render_feeds<F>(repo: &Repo, filter: F) -> Result<Response>
+where
+    F: Fn(Option<&BTreeSet<String>>) -> bool,
+{
+    let tags = get_tags_by_feed(repo)?;
+    let subs = repo.get_subscriptions()?;
+    let mut feeds = vec![];
+    for sub in &subs {
+        let sub = sub.value()?;
+        let feed_tags = tags.get(&sub.feed_id);
+        if filter(feed_tags) {
+            let options = repo.get_feed_options(sub.feed_id)?;
+            feeds.push(json!(Feed::new(repo.user_id(), &sub, feed_tags, options)));
+        }
+    }
+    Ok(Json(feeds).into_response())
+}
+
+// entry IDs are entry sequence numbers, only the sort keys of the matching entries are kept
+// and the entries are loaded for the requested page
+fn render_entries(repo: &Repo, filter: &EntryFilter) -> Result<Response> {
+    let context = Context::new(repo)?;
+    let range = filter.seq_range();
+    let entries: Box<dyn Iterator<Item = Result<(u64, Value<FeedEntry>)>>> = match &filter.search {
+        Some(text) => {
+            let mut entries = vec![];
+            for id in repo.search_text(text)? {
+                if let (Some(seq), Some(entry)) = (repo.get_entry_seq(id)?, repo.get_entry(id)?) {
+                    if range.contains(&seq) {
+                        entries.push(Ok((seq, entry)));
+                    }
+                }
+            }
+            Box::new(entries.into_iter())
+        }
+        None => Box::new(repo.entries_by_seq(range)),
+    };
+
+    let mut selected = vec![];
+    for item in entries {
+        let (seq, item) = item?;
+        let entry = item.value()?;
+        if !filter.matches(&entry, &context) {
+            continue;
+        }
+        // entries are numbered in the order they were created in
+        let key = match filter.order.as_str() {
+            "id" | "created_at" => 0,
+            "status" => i128::from(!context.unread.contains(&entry.id)),
+            _ => entry.published.unix_timestamp_nanos(),
+        };
+        selected.push((key, seq));
+    }
+    if filter.descending {
+        selected.sort_unstable_by(|a, b| b.cmp(a));
+    } else {
+        selected.sort_unstable();
+    }
+
+    let total = selected.len();
+    let mut res = vec![];
+    for (_, seq) in selected.into_iter().skip(filter.offset).take(filter.limit) {
+        if let Some(entry) = repo.get_entry_by_seq(seq)? {
+            res.push(render_entry(repo, &context, seq, &entry.value()?)?);
+        }
+    }
+    Ok(Json(json!({ "total": total, "entries": res })).into_response())
+}
+
+fn render_entry(repo: &Repo, context: &Context, seq: u64, entry: &Entry<'_>) -> Result<serde_json::Value> {
+    let extras = repo.get_entry_extras(entry.id)?;
+    let extras = extras.as_ref().map(|extras| extras.value()).transpose()?;
+    let enclosures = extras
+        .as_ref()
+        .and_then(|extras| extras.enclosure.as_ref())
+        .map(|enclosure| {
+            json!({
+                "id": seq,
+                "user_id": repo.user_id(),
+                "entry_id": seq,
+                "url": enclosure.enclosure_url,
+                "mime_type": enclosure.enclosure_type.unwrap_or_default(),
+                "size": enclosure.size(),
+            })
+        })
+        .into_iter()
+        .collect::<Vec<_>>();
+    let feed = context.feeds.get(&entry.feed_id);
+    let content = entry.content.or(entry.summary).unwrap_or_default();
+    let words = html::strip_tags(content).split_whitespace().count();
+    let changed_at = repo
+        .get_modified(entry.id)?
+        .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok())
+        .unwrap_or(entry.created_at)
+        .max(entry.created_at);
+
+    let res = EntryView {
+        id: seq,
+        user_id: repo.user_id(),
+        feed_id: entry.feed_id,
+        status: if context.unread.contains(&entry.id) {
+            "unread"
+        } else {
+            "read"
+        },
+        hash: md5_hex(entry.id.0.to_string().as_bytes()),
+        title: entry.title.unwrap_or_default(),
+        url: entry.url.unwrap_or_default(),
+        comments_url: "",
+        published_at: entry.published,
+        created_at: entry.created_at,
+        changed_at,
+        content,
+        author: entry.author.unwrap_or_default(),
+        share_code: "",
+        starred: context.starred.contains(&entry.id),
+        reading_time: words / WORDS_PER_MINUTE,
+        enclosures,
+        feed,
+        tags: vec![],
+    };
+    Ok(json!(res))
+}
+
+// entry IDs are entry sequence numbers, entries the user can't read are skipped
+fn get_entry_ids<I: IntoIterator<Item = u64>>(repo: &Repo, entry_ids: I) -> Result<Vec<EntryId>> {
+    let mut ids = vec![];
+    for seq in entry_ids {
+        if let Some(entry) = repo.get_entry_by_seq(seq)? {
+            ids.push(entry.value()?.id);
+        }
+    }
+    Ok(ids)
+}
+
+// looks up a category by ID, the inner None stands for the default category
+fn find_category(repo: &Repo, id: u32) -> Result<Option<Option<String>>> {
+    if id == DEFAULT_CATEGORY_ID {
+        return Ok(Some(None));
+    }
+    Ok(repo
+        .get_folders()?
+        .into_iter()
+        .find(|name| tag_id(name) == id)
+        .map(Some))
+}
+
+fn get_category_feed_ids(repo: &Repo, category_id: u32) -> Result<Vec<FeedId>> {
+    let tags = get_tags_by_feed(repo)?;
+    let mut res = vec![];
+    for sub in repo.get_subscriptions()? {
+        let feed_id = sub.value()?.feed_id;
+        if category_of(tags.get(&feed_id)).0 == category_id {
+            res.push(feed_id);
+        }
+    }
+    Ok(res)
+}
+
+fn get_tags_by_feed(repo: &Repo) -> Result<HashMap<FeedId, BTreeSet<String>>> {
+    let mut res: HashMap<FeedId, BTreeSet<String>> = HashMap::new();
+    for tagging in repo.get_taggings()? {
+        let tagging = tagging.value()?;
+        res.entry(tagging.feed_id)
+            .or_default()
+            .insert(tagging.name.to_owned());
+    }
+    Ok(res)
+}
+
+// feeds with multiple tags are assigned to the first one
+fn category_of(tags: Option<&BTreeSet<String>>) -> (u32, String) {
+    match tags.and_then(|tags| tags.iter().next()) {
+        Some(name) => (tag_id(name), name.clone()),
+        None => (DEFAULT_CATEGORY_ID, DEFAULT_CATEGORY_TITLE.to_owned()),
+    }
+}
+
+fn not_found() -> Response {
+    error(StatusCode::NOT_FOUND, "Resource not found.")
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error_message": message }))).into_response()
+}
+
+// state shared by all entries of a response
+struct Context {
+    unread: HashSet<EntryId>,
+    starred: HashSet<EntryId>,
+    feeds: HashMap<FeedId, serde_json::Value>,
+}
+
+impl Context {
+    fn new(repo: &Repo) -> Result<Self> {
+        let unread = repo
+            .get_unread()?
+            .iter()
+            .map(|key| key.key())
+            .collect::<Result<_, _>>()?;
+        let starred = repo
+            .get_starred()?
+            .iter()
+            .map(|key| key.key())
+            .collect::<Result<_, _>>()?;
+        let tags = get_tags_by_feed(repo)?;
+        let mut feeds = HashMap::new();
+        for sub in repo.get_subscriptions()? {
+            let sub = sub.value()?;
+            let options = repo.get_feed_options(sub.feed_id)?;
+            feeds.insert(
+                sub.feed_id,
+                json!(Feed::new(repo.user_id(), &sub, tags.get(&sub.feed_id), options)),
+            );
+        }
+        Ok(Self {
+            unread,
+            starred,
+            feeds,
+        })
+    }
+}
+
+struct EntryFilter {
+    statuses: Vec<String>,
+    starred: Option<bool>,
+    search: Option<String>,
+    feeds: Option<Vec<FeedId>>,
+    after: Option<i64>,
+    before: Option<i64>,
+    after_entry_id: Option<u64>,
+    before_entry_id: Option<u64>,
+    order: String,
+    descending: bool,
+    offset: usize,
+    limit: usize,
+}
+
+impl EntryFilter {
+    fn new(params: &[(String, String)]) -> Self {
+        let param = |key| {
+            params
+                .iter()
+                .find_map(|(k, val)| (k == key).then_some(val.as_str()))
+        };
+        Self {
+            statuses: params
+                .iter()
+                .filter(|(key, _)| key == "status")
+                .map(|(_, val)| val.clone())
+                .collect(),
+            starred: param("starred").map(|str| str == "true" || str == "1"),
+            search: param("search").filter(|str| !str.is_empty()).map(str::to_owned),
+            feeds: None,
+            after: param("after").and_then(|str| str.parse().ok()),
+            before: param("before").and_then(|str| str.parse().ok()),
+            after_entry_id: param("after_entry_id").and_then(|str| str.parse().ok()),
+            before_entry_id: param("before_entry_id").and_then(|str| str.parse().ok()),
+            order: param("order").unwrap_or("published_at").to_owned(),
+            descending: param("direction") == Some("desc"),
+            offset: param("offset").and_then(|str| str.parse().ok()).unwrap_or(0),
+            limit: param("limit")
+                .and_then(|str| str.parse().ok())
+                .filter(|limit| *limit > 0)
+                .unwrap_or(DEFAULT_LIMIT),
+        }
+    }
+
+    fn matches(&self, entry: &Entry<'_>, context: &Context) -> bool {
+        let status = if context.unread.contains(&entry.id) {
+            "unread"
+        } else {
+            "read"
+        };
+        let published = entry.published.unix_timestamp();
+        (self.statuses.is_empty() || self.statuses.iter().any(|str| str == status))
+            && self
+                .starred
+                .map_or(true, |starred| context.starred.contains(&entry.id) == starred)
+            && self
+                .feeds
+                .as_ref()
+                .map_or(true, |feeds| feeds.contains(&entry.feed_id))
+            && self.after.map_or(true, |after| published > after)
+            && self.before.map_or(true, |before| published < before)
+    }
+
+    fn seq_range(&self) -> (Bound<u64>, Bound<u64>) {
+        let start = self.after_entry_id.map_or(Bound::Unbounded, Bound::Excluded);
+        let end = self.before_entry_id.map_or(Bound::Unbounded, Bound::Excluded);
+        (start, end)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Category {
+    id: u32,
+    title: String,
+    user_id: u64,
+}
+
+impl Category {
+    fn new(user_id: u64, title: String) -> Self {
+        Self {
+            id: tag_id(&title),
+            title,
+            user_id,
+        }
+    }
+
+    fn default_category(user_id: u64) -> Self {
+        Self {
+            id: DEFAULT_CATEGORY_ID,
+            title: DEFAULT_CATEGORY_TITLE.to_owned(),
+            user_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Feed<'a> {
+    id: FeedId,
+    user_id: u64,
+    feed_url: &'a str,
+    site_url: &'a str,
+    title: &'a str,
+    #[serde(with = "codecs::rfc3339_date")]
+    checked_at: OffsetDateTime,
+    parsing_error_message: &'a str,
+    parsing_error_count: u32,
+    crawler: bool,
+    disabled: bool,
+    category: Category,
+    icon: Option<()>,
+}
+
+impl<'a> Feed<'a> {
+    fn new(
+        user_id: u64,
+        sub: &Subscription<'a>,
+        tags: Option<&BTreeSet<String>>,
+        options: FeedOptions,
+    ) -> Self {
+        let (id, title) = category_of(tags);
+        Self {
+            id: sub.feed_id,
+            user_id,
+            feed_url: sub.feed_url,
+            site_url: sub.site_url,
+            title: sub.title,
+            checked_at: sub.created_at,
+            parsing_error_message: "",
+            parsing_error_count: 0,
+            crawler: options.extract_content,
+            disabled: false,
+            category: Category {
+                id,
+                title,
+                user_id,
+            },
+            icon: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EntryView<'a> {
+    id: u64,
+    user_id: u64,
+    feed_id: FeedId,
+    status: &'static str,
+    hash: String,
+    title: &'a str,
+    url: &'a str,
+    comments_url: &'a str,
+    #[serde(with = "codecs::rfc3339_date")]
+    published_at: OffsetDateTime,
+    #[serde(with = "codecs::rfc3339_date")]
+    created_at: OffsetDateTime,
+    #[serde(with = "codecs::rfc3339_date")]
+    changed_at: OffsetDateTime,
+    content: &'a str,
+    author: &'a str,
+    share_code: &'a str,
+    starred: bool,
+    reading_time: usize,
+    enclosures: Vec<serde_json::Value>,
+    feed: Option<&'a serde_json::Value>,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddFeed {
+    feed_url: String,
+    category_id: Option<u32>,
+    #[serde(default)]
+    crawler: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateFeed {
+    title: Option<String>,
+    category_id: Option<u32>,
+    crawler: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddCategory {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateEntries {
+    entry_ids: Vec<u64>,
+    status: String,
+}
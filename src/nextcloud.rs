@@ -0,0 +1,672 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sled_bincode::Value;
+use time::OffsetDateTime;
+
+use crate::digest::md5_hex;
+use crate::refresh::add_feed;
+use crate::repo::{FeedEntry, Repo};
+use crate::result::Result;
+use crate::types::{tag_id, Entry, EntryId, FeedId, Subscription, Tagging};
+
+const API_LEVEL: &str = "v1-3";
+const TYPE_FEED: u8 = 0;
+const TYPE_FOLDER: u8 = 1;
+const TYPE_STARRED: u8 = 2;
+
+// a Nextcloud News API compatible endpoint, clients are configured with `[grunt-host]/nextcloud`
+// and authenticate with the BasicAuth credentials, tags are exposed as folders
+pub fn router() -> Router {
+    let api = Router::new()
+        .route("/version", get(get_version))
+        .route("/status", get(get_status))
+        .route("/user", get(get_user))
+        .route("/folders", get(get_folders).post(create_folder))
+        .route("/folders/:id", delete(delete_folder).put(rename_folder))
+        .route("/folders/:id/read", post(mark_folder_read).put(mark_folder_read))
+        .route("/feeds", get(get_feeds).post(create_feed))
+        .route("/feeds/:id", delete(delete_feed))
+        .route("/feeds/:id/move", post(move_feed).put(move_feed))
+        .route("/feeds/:id/rename", post(rename_feed).put(rename_feed))
+        .route("/feeds/:id/read", post(mark_feed_read).put(mark_feed_read))
+        .route("/items", get(get_items))
+        .route("/items/updated", get(get_updated_items))
+        .route("/items/read", post(mark_all_read).put(mark_all_read))
+        .route("/items/:id/read", post(mark_item_read).put(mark_item_read))
+        .route("/items/:id/unread", post(mark_item_unread).put(mark_item_unread))
+        .route("/items/:id/star", post(star_item).put(star_item))
+        .route("/items/:id/unstar", post(unstar_item).put(unstar_item))
+        .route("/items/read/multiple", post(mark_items_read).put(mark_items_read))
+        .route(
+            "/items/unread/multiple",
+            post(mark_items_unread).put(mark_items_unread),
+        )
+        .route("/items/star/multiple", post(star_items).put(star_items))
+        .route("/items/unstar/multiple", post(unstar_items).put(unstar_items));
+
+    Router::new()
+        .route("/index.php/apps/news/api", get(get_api_levels))
+        .nest(&format!("/index.php/apps/news/api/{API_LEVEL}"), api)
+}
+
+async fn get_api_levels() -> Json<serde_json::Value> {
+    Json(json!({ "apiLevels": [API_LEVEL] }))
+}
+
+async fn get_version() -> Json<serde_json::Value> {
+    Json(json!({ "version": env!("CARGO_PKG_VERSION") }))
+}
+
+async fn get_status() -> Json<serde_json::Value> {
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "warnings": { "improperlyConfiguredCron": false, "incorrectDbCharset": false },
+    }))
+}
+
+async fn get_user(Extension(repo): Extension<Arc<Repo>>) -> Json<serde_json::Value> {
+    Json(json!({
+        "userId": repo.user_name(),
+        "displayName": repo.user_name(),
+        "lastLoginTimestamp": OffsetDateTime::now_utc().unix_timestamp(),
+        "avatar": null,
+    }))
+}
+
+async fn get_folders(Extension(repo): Extension<Arc<Repo>>) -> Result<Json<serde_json::Value>> {
+    let names = repo.get_folders()?;
+    let folders = names.iter().map(|name| Folder::new(name)).collect::<Vec<_>>();
+    Ok(Json(json!({ "folders": folders })))
+}
+
+async fn create_folder(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(add_folder): Json<AddFolder>,
+) -> Result<Response> {
+    if add_folder.name.is_empty() {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, "folder name is empty").into_response());
+    }
+    if repo.get_folders()?.contains(&add_folder.name) {
+        return Ok((StatusCode::CONFLICT, "folder already exists").into_response());
+    }
+    repo.add_folder(&add_folder.name)?;
+    Ok(Json(json!({ "folders": [Folder::new(&add_folder.name)] })).into_response())
+}
+
+async fn rename_folder(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(folder_id): Path<u32>,
+    Json(add_folder): Json<AddFolder>,
+) -> Result<StatusCode> {
+    let name = match find_folder(&repo, folder_id)? {
+        Some(name) => name,
+        None => return Ok(StatusCode::NOT_FOUND),
+    };
+    if add_folder.name.is_empty() {
+        return Ok(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    if repo.get_folders()?.contains(&add_folder.name) {
+        return Ok(StatusCode::CONFLICT);
+    }
+    repo.rename_tag(&name, &add_folder.name)?;
+    Ok(StatusCode::OK)
+}
+
+// feeds in the folder are only untagged, not unsubscribed
+async fn delete_folder(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(folder_id): Path<u32>,
+) -> Result<StatusCode> {
+    match find_folder(&repo, folder_id)? {
+        Some(name) => {
+            repo.delete_tag(&name)?;
+            Ok(StatusCode::OK)
+        }
+        None => Ok(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn mark_folder_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(folder_id): Path<u32>,
+    Json(newest): Json<NewestItem>,
+) -> Result<StatusCode> {
+    let name = match find_folder(&repo, folder_id)? {
+        Some(name) => name,
+        None => return Ok(StatusCode::NOT_FOUND),
+    };
+    let feeds = repo.get_feeds_by_tags(&[name])?;
+    mark_read_until(
+        &repo,
+        |entry| feeds.contains(&entry.feed_id),
+        newest.newest_item_id,
+    )?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_feeds(Extension(repo): Extension<Arc<Repo>>) -> Result<Json<serde_json::Value>> {
+    let tags = get_tags_by_feed(&repo)?;
+    let mut unread_by_feed: HashMap<FeedId, usize> = HashMap::new();
+    for key in repo.get_unread()? {
+        if let Some(entry) = repo.get_entry(key.key()?)? {
+            *unread_by_feed.entry(entry.value()?.feed_id).or_default() += 1;
+        }
+    }
+    let newest_item_id = match repo.entries_by_seq(..).next_back() {
+        Some(res) => Some(res?.0),
+        None => None,
+    };
+
+    let subs = repo.get_subscriptions()?;
+    let mut feeds = vec![];
+    for sub in &subs {
+        let sub = sub.value()?;
+        let unread_count = unread_by_feed.get(&sub.feed_id).copied().unwrap_or_default();
+        feeds.push(json!(Feed::new(&sub, tags.get(&sub.feed_id), unread_count)));
+    }
+    let res = json!({
+        "feeds": feeds,
+        "starredCount": repo.get_starred()?.len(),
+        "newestItemId": newest_item_id,
+    });
+    Ok(Json(res))
+}
+
+async fn create_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(add_feed_req): Json<AddFeed>,
+) -> Result<Response> {
+    if repo.get_subscription_by_url(&add_feed_req.url)?.is_some() {
+        return Ok((StatusCode::CONFLICT, "feed already exists").into_response());
+    }
+    let id = match add_feed(&repo, &add_feed_req.url).await? {
+        Some(id) => id,
+        None => return Ok((StatusCode::UNPROCESSABLE_ENTITY, "feed could not be read").into_response()),
+    };
+    if let Some(name) = add_feed_req
+        .folder_id
+        .map(|id| find_folder(&repo, id))
+        .transpose()?
+        .flatten()
+    {
+        let tagging_id = repo.new_tagging_id()?;
+        repo.add_tagging(&Tagging::new(tagging_id, id, &name))?;
+    }
+
+    let tags = get_tags_by_feed(&repo)?;
+    let sub = match repo.get_subscription(id)? {
+        Some(sub) => sub,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let sub = sub.value()?;
+    let unread_count = repo
+        .get_unread_ids_by_feeds(&[id], OffsetDateTime::now_utc())?
+        .len();
+    let feed = Feed::new(&sub, tags.get(&id), unread_count);
+    Ok(Json(json!({ "feeds": [feed] })).into_response())
+}
+
+async fn delete_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+) -> Result<StatusCode> {
+    if repo.get_subscription(feed_id)?.is_none() {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    repo.delete_subscription(feed_id)?;
+    Ok(StatusCode::OK)
+}
+
+// a feed can be tagged multiple times, but it can only be in one folder
+async fn move_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+    Json(move_feed): Json<MoveFeed>,
+) -> Result<StatusCode> {
+    if repo.get_subscription(feed_id)?.is_none() {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    let name = match move_feed.folder_id.filter(|id| *id != 0) {
+        Some(id) => match find_folder(&repo, id)? {
+            Some(name) => Some(name),
+            None => return Ok(StatusCode::NOT_FOUND),
+        },
+        None => None,
+    };
+
+    for tagging in repo.get_taggings()? {
+        let tagging = tagging.value()?;
+        if tagging.feed_id == feed_id {
+            repo.delete_tagging(tagging.id)?;
+        }
+    }
+    if let Some(name) = name {
+        repo.add_tagging(&Tagging::new(repo.new_tagging_id()?, feed_id, &name))?;
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn rename_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+    Json(rename_feed): Json<RenameFeed>,
+) -> Result<StatusCode> {
+    match repo.rename_subscription(feed_id, &rename_feed.feed_title)? {
+        Some(_) => Ok(StatusCode::OK),
+        None => Ok(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn mark_feed_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+    Json(newest): Json<NewestItem>,
+) -> Result<StatusCode> {
+    mark_read_until(&repo, |entry| entry.feed_id == feed_id, newest.newest_item_id)?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_items(
+    Extension(repo): Extension<Arc<Repo>>,
+    Query(query): Query<ItemsQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let state = State::new(&repo)?;
+    let feeds = get_selected_feeds(&repo, query.kind, query.id)?;
+    let starred_only = query.kind == TYPE_STARRED;
+
+    // item IDs are entry sequence numbers, the offset is the ID of the last item the client has seen
+    let after = match query.offset.filter(|offset| *offset != 0) {
+        Some(offset) => Bound::Excluded(offset),
+        None => Bound::Unbounded,
+    };
+    let entries: Box<dyn Iterator<Item = _>> = if query.oldest_first {
+        Box::new(repo.entries_by_seq((after, Bound::Unbounded)))
+    } else {
+        Box::new(repo.entries_by_seq((Bound::Unbounded, after)).rev())
+    };
+    // a negative batch size means no limit
+    let limit = usize::try_from(query.batch_size).unwrap_or(usize::MAX);
+
+    let mut items = vec![];
+    for item in entries {
+        if items.len() == limit {
+            break;
+        }
+        let (seq, item) = item?;
+        let entry = item.value()?;
+        let matches = feeds
+            .as_ref()
+            .map_or(true, |feeds| feeds.contains(&entry.feed_id))
+            && (!starred_only || state.starred.contains(&entry.id))
+            && (query.get_read || state.unread.contains(&entry.id));
+        if matches {
+            items.push((seq, item));
+        }
+    }
+
+    Ok(Json(json!({ "items": render_items(&repo, &state, &items)? })))
+}
+
+async fn get_updated_items(
+    Extension(repo): Extension<Arc<Repo>>,
+    Query(query): Query<UpdatedItemsQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let state = State::new(&repo)?;
+    let feeds = get_selected_feeds(&repo, query.kind, query.id)?;
+    let starred_only = query.kind == TYPE_STARRED;
+    // some clients send the timestamp in microseconds
+    let since = if query.last_modified > 10_000_000_000 {
+        query.last_modified / 1_000_000
+    } else {
+        query.last_modified
+    };
+    let matches = |entry: &Entry<'_>| {
+        feeds
+            .as_ref()
+            .map_or(true, |feeds| feeds.contains(&entry.feed_id))
+            && (!starred_only || state.starred.contains(&entry.id))
+    };
+
+    let mut items = vec![];
+    // entries are numbered in the order they were created in, so new entries come first
+    for item in repo.entries_by_seq(..).rev() {
+        let (seq, item) = item?;
+        let entry = item.value()?;
+        if entry.created_at.unix_timestamp() <= since {
+            break;
+        }
+        if matches(&entry) {
+            items.push((seq, item));
+        }
+    }
+    // older entries that were marked since
+    for id in repo.get_modified_since(since)? {
+        let (seq, item) = match (repo.get_entry_seq(id)?, repo.get_entry(id)?) {
+            (Some(seq), Some(item)) => (seq, item),
+            _ => continue,
+        };
+        let entry = item.value()?;
+        if entry.created_at.unix_timestamp() <= since && matches(&entry) {
+            items.push((seq, item));
+        }
+    }
+    items.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+
+    Ok(Json(json!({ "items": render_items(&repo, &state, &items)? })))
+}
+
+async fn mark_all_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(newest): Json<NewestItem>,
+) -> Result<StatusCode> {
+    mark_read_until(&repo, |_| true, newest.newest_item_id)?;
+    Ok(StatusCode::OK)
+}
+
+async fn mark_item_read(Extension(repo): Extension<Arc<Repo>>, Path(id): Path<u64>) -> Result<StatusCode> {
+    repo.delete_unread(get_entry_ids(&repo, [id])?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn mark_item_unread(Extension(repo): Extension<Arc<Repo>>, Path(id): Path<u64>) -> Result<StatusCode> {
+    repo.add_unread(get_entry_ids(&repo, [id])?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn star_item(Extension(repo): Extension<Arc<Repo>>, Path(id): Path<u64>) -> Result<StatusCode> {
+    repo.add_starred(get_entry_ids(&repo, [id])?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn unstar_item(Extension(repo): Extension<Arc<Repo>>, Path(id): Path<u64>) -> Result<StatusCode> {
+    repo.delete_starred(get_entry_ids(&repo, [id])?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn mark_items_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(items): Json<ItemIds>,
+) -> Result<StatusCode> {
+    repo.delete_unread(get_entry_ids(&repo, items.item_ids)?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn mark_items_unread(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(items): Json<ItemIds>,
+) -> Result<StatusCode> {
+    repo.add_unread(get_entry_ids(&repo, items.item_ids)?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn star_items(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(items): Json<ItemIds>,
+) -> Result<StatusCode> {
+    repo.add_starred(get_entry_ids(&repo, items.item_ids)?)?;
+    Ok(StatusCode::OK)
+}
+
+async fn unstar_items(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(items): Json<ItemIds>,
+) -> Result<StatusCode> {
+    repo.delete_starred(get_entry_ids(&repo, items.item_ids)?)?;
+    Ok(StatusCode::OK)
+}
+
+// marks unread entries as read up to the newest item the client has seen, later items stay unread
+fn mark_read_until<F>(repo: &Repo, filter: F, newest_item_id: u64) -> Result<()>
+where
+    F: Fn(&Entry<'_>) -> bool,
+{
+    let mut ids = vec![];
+    for key in repo.get_unread()? {
+        let id = key.key()?;
+        let entry = match repo.get_entry(id)? {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if matches!(repo.get_entry_seq(id)?, Some(seq) if seq <= newest_item_id) && filter(&entry.value()?) {
+            ids.push(id);
+        }
+    }
+    repo.delete_unread(ids)
+}
+
+// item IDs are entry sequence numbers, items the user can't read are skipped
+fn get_entry_ids<I: IntoIterator<Item = u64>>(repo: &Repo, item_ids: I) -> Result<Vec<EntryId>> {
+    let mut ids = vec![];
+    for seq in item_ids {
+        if let Some(entry) = repo.get_entry_by_seq(seq)? {
+            ids.push(entry.value()?.id);
+        }
+    }
+    Ok(ids)
+}
+
+// returns None when all feeds are selected
+fn get_selected_feeds(repo: &Repo, kind: u8, id: u64) -> Result<Option<Vec<FeedId>>> {
+    match kind {
+        TYPE_FEED => Ok(Some(vec![FeedId(id)])),
+        TYPE_FOLDER => {
+            let name = u32::try_from(id)
+                .ok()
+                .map(|id| find_folder(repo, id))
+                .transpose()?
+                .flatten();
+            match name {
+                Some(name) => Ok(Some(repo.get_feeds_by_tags(&[name])?)),
+                None => Ok(Some(vec![])),
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+fn find_folder(repo: &Repo, id: u32) -> Result<Option<String>> {
+    Ok(repo.get_folders()?.into_iter().find(|name| tag_id(name) == id))
+}
+
+fn get_tags_by_feed(repo: &Repo) -> Result<HashMap<FeedId, BTreeSet<String>>> {
+    let mut res: HashMap<FeedId, BTreeSet<String>> = HashMap::new();
+    for tagging in repo.get_taggings()? {
+        let tagging = tagging.value()?;
+        res.entry(tagging.feed_id)
+            .or_default()
+            .insert(tagging.name.to_owned());
+    }
+    Ok(res)
+}
+
+fn render_items(repo: &Repo, state: &State, entries: &[(u64, Value<FeedEntry>)]) -> Result<Vec<serde_json::Value>> {
+    let mut items = vec![];
+    for (seq, entry) in entries {
+        let entry = entry.value()?;
+        let id = entry.id;
+        let extras = repo.get_entry_extras(id)?;
+        let extras = extras.as_ref().map(|extras| extras.value()).transpose()?;
+        let enclosure = extras.as_ref().and_then(|extras| extras.enclosure.as_ref());
+        let modified = repo.get_modified(id)?.unwrap_or_default();
+        let body = entry.content.or(entry.summary).unwrap_or_default();
+        let guid = entry.id.0.to_string();
+
+        items.push(json!({
+            "id": seq,
+            "guid": guid,
+            "guidHash": md5_hex(guid.as_bytes()),
+            "url": entry.url,
+            "title": entry.title,
+            "author": entry.author,
+            "pubDate": entry.published.unix_timestamp(),
+            "updatedDate": null,
+            "body": body,
+            "enclosureMime": enclosure.and_then(|enclosure| enclosure.enclosure_type),
+            "enclosureLink": enclosure.map(|enclosure| enclosure.enclosure_url),
+            "mediaThumbnail": entry.image.as_ref().map(|image| image.url),
+            "mediaDescription": null,
+            "feedId": entry.feed_id,
+            "unread": state.unread.contains(&id),
+            "starred": state.starred.contains(&id),
+            "rtl": false,
+            "lastModified": entry.created_at.unix_timestamp().max(modified),
+            "fingerprint": md5_hex(body.as_bytes()),
+        }));
+    }
+    Ok(items)
+}
+
+struct State {
+    unread: HashSet<EntryId>,
+    starred: HashSet<EntryId>,
+}
+
+impl State {
+    fn new(repo: &Repo) -> Result<Self> {
+        let unread = repo
+            .get_unread()?
+            .iter()
+            .map(|key| key.key())
+            .collect::<Result<_, _>>()?;
+        let starred = repo
+            .get_starred()?
+            .iter()
+            .map(|key| key.key())
+            .collect::<Result<_, _>>()?;
+        Ok(Self { unread, starred })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Folder<'a> {
+    id: u32,
+    name: &'a str,
+}
+
+impl<'a> Folder<'a> {
+    fn new(name: &'a str) -> Self {
+        Self {
+            id: tag_id(name),
+            name,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Feed<'a> {
+    id: FeedId,
+    url: &'a str,
+    title: &'a str,
+    favicon_link: Option<&'a str>,
+    added: i64,
+    folder_id: Option<u32>,
+    unread_count: usize,
+    ordering: u8,
+    link: &'a str,
+    pinned: bool,
+    update_error_count: u32,
+    last_update_error: Option<&'a str>,
+}
+
+impl<'a> Feed<'a> {
+    fn new(sub: &Subscription<'a>, tags: Option<&BTreeSet<String>>, unread_count: usize) -> Self {
+        Self {
+            id: sub.feed_id,
+            url: sub.feed_url,
+            title: sub.title,
+            favicon_link: None,
+            added: sub.created_at.unix_timestamp(),
+            folder_id: tags.and_then(|tags| tags.iter().next()).map(|name| tag_id(name)),
+            unread_count,
+            ordering: 0,
+            link: sub.site_url,
+            pinned: false,
+            update_error_count: 0,
+            last_update_error: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AddFolder {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddFeed {
+    url: String,
+    folder_id: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveFeed {
+    folder_id: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameFeed {
+    feed_title: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewestItem {
+    newest_item_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemIds {
+    item_ids: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemsQuery {
+    #[serde(default = "default_batch_size")]
+    batch_size: i64,
+    offset: Option<u64>,
+    #[serde(rename = "type", default = "default_type")]
+    kind: u8,
+    #[serde(default)]
+    id: u64,
+    #[serde(default = "default_true")]
+    get_read: bool,
+    #[serde(default)]
+    oldest_first: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdatedItemsQuery {
+    last_modified: i64,
+    #[serde(rename = "type", default = "default_type")]
+    kind: u8,
+    #[serde(default)]
+    id: u64,
+}
+
+fn default_batch_size() -> i64 {
+    -1
+}
+
+fn default_type() -> u8 {
+    3
+}
+
+fn default_true() -> bool {
+    true
+}
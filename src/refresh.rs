@@ -1,37 +1,231 @@
-use futures_util::future::join_all;
-use rsst::client::{RssClient, RssRequest};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures_util::stream::{self, StreamExt};
 use rsst::feed::Feed;
 use time::OffsetDateTime;
 
-use crate::repo::Repo;
-use crate::result::Result;
-use crate::types::{Entry, FeedId};
+use crate::extract::{fetch_article, Article};
+use crate::fetch::{HttpClient, Page};
+use crate::repo::{Repo, Store};
+use crate::result::{Result, ServiceEror};
+use crate::types::{Entry, EntryExtras, ExtractedArticle, FeedId, Subscription};
+
+// number of pages downloaded at the same time during a refresh
+const MAX_CONCURRENT_FETCHES: usize = 8;
+// new entries beyond this limit keep the content of the feed, so that a feed that
+// publishes many entries at once does not hold up the refresh
+const MAX_EXTRACTIONS_PER_REFRESH: usize = 20;
 
-pub async fn refresh_all_feeds(repo: &Repo) -> Result<()> {
+pub async fn refresh_all_feeds(store: &Arc<Store>) -> Result<()> {
     tracing::info!("refreshing all subscriptions");
 
-    let client = RssClient::default();
-    let mut tasks = vec![];
-    for res in repo.get_subscriptions()? {
+    let mut feeds = vec![];
+    for res in store.get_subscriptions()? {
         let sub = res.value()?;
-        let task = client.exec(RssRequest::new(sub.feed_url)?);
-        tasks.push(async move { task.await.map(|res| (sub.feed_id, res)) })
+        if sub.feed_id != FeedId::PAGES {
+            feeds.push((sub.feed_id, sub.feed_url.to_owned()));
+        }
     }
-    for res in join_all(tasks).await {
-        match res {
-            Ok((feed_id, resp)) => refresh_feed(repo, feed_id, resp.borrow_feed())?,
-            Err(err) => tracing::error!("failed to retrieve a feed: {err}"),
+    let client = &HttpClient::default();
+    let tasks = feeds
+        .into_iter()
+        .map(|(feed_id, feed_url)| async move { (feed_id, client.get(&feed_url).await) })
+        .collect::<Vec<_>>();
+    let pages = stream::iter(tasks)
+        .buffer_unordered(MAX_CONCURRENT_FETCHES)
+        .collect::<Vec<_>>()
+        .await;
+    for (feed_id, res) in pages {
+        let page = match res {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::error!("failed to retrieve a feed: {err}");
+                continue;
+            }
+        };
+        match parse_feed(&page) {
+            Ok(feed) => refresh_feed(store, feed_id, &feed).await?,
+            Err(err) => tracing::error!("failed to parse the feed at {}: {err}", page.url),
         }
     }
     Ok(())
 }
 
-pub fn refresh_feed(repo: &Repo, id: FeedId, feed: &Feed<'_>) -> Result<()> {
+pub async fn refresh_subscription(repo: &Repo, id: FeedId, feed_url: &str) -> Result<()> {
+    let page = HttpClient::default().get(feed_url).await?;
+    refresh_feed(repo.store(), id, &parse_feed(&page)?).await
+}
+
+// feeds are downloaded with the limits of the HTTP client and parsed once the body is complete
+pub fn parse_feed(page: &Page) -> Result<Feed<'_>> {
+    let text = std::str::from_utf8(&page.body).map_err(|err| ServiceEror::InvalidFeed(err.to_string()))?;
+    roxmltree_serde::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|err| ServiceEror::InvalidFeed(err.to_string()))
+}
+
+// subscribes to a feed, returns `None` when the URL does not point at a valid feed
+pub async fn add_feed(repo: &Repo, feed_url: &str) -> Result<Option<FeedId>> {
     let created_at = OffsetDateTime::now_utc();
+    let page = match HttpClient::default().get(feed_url).await {
+        Ok(page) => page,
+        Err(err) => {
+            tracing::info!("could not retrieve a feed from {feed_url}: {err}");
+            return Ok(None);
+        }
+    };
+    let feed = match parse_feed(&page) {
+        Ok(feed) => feed,
+        Err(err) => {
+            tracing::info!("could not parse the feed at {feed_url}: {err}");
+            return Ok(None);
+        }
+    };
+    let store = repo.store();
+    // feeds other users are subscribed to are shared, so that their entries are only stored once
+    let id = match store.get_feed_id(feed_url)? {
+        Some(id) => id,
+        None => store.new_feed_id()?,
+    };
+    // entries stored before the subscription are new to the user
+    let mut stored = vec![];
     for item in &feed.channel.items {
-        if let Some(entry) = Entry::from_item(id, item, created_at) {
-            repo.insert_entry(entry)?;
+        match Entry::from_item(id, item, created_at) {
+            Some(entry) if store.has_entry(entry.id)? => stored.push(entry.id),
+            _ => {}
         }
     }
+    let sub = Subscription::from_feed(id, &feed, feed_url, created_at);
+    repo.add_subscription(&sub)?;
+    repo.add_unread(stored)?;
+    refresh_feed(store, id, &feed).await?;
+
+    tracing::info!("successfully added a subscription for {}", sub.feed_url);
+    Ok(Some(id))
+}
+
+pub async fn refresh_feed(store: &Arc<Store>, id: FeedId, feed: &Feed<'_>) -> Result<()> {
+    let created_at = OffsetDateTime::now_utc();
+    let options = store.get_feed_options(id)?;
+    let entries = feed
+        .channel
+        .items
+        .iter()
+        .filter_map(|item| Some((Entry::from_item(id, item, created_at)?, item)))
+        .collect::<Vec<_>>();
+
+    let mut articles = HashMap::new();
+    if options.extract_content {
+        // stored entries already have their content extracted
+        let mut pending = vec![];
+        for (entry, _) in &entries {
+            match entry.url {
+                Some(url) if !store.has_entry(entry.id)? => pending.push((entry.id, url)),
+                _ => {}
+            }
+        }
+        pending.truncate(MAX_EXTRACTIONS_PER_REFRESH);
+
+        let client = &HttpClient::default();
+        let tasks = pending
+            .into_iter()
+            .map(|(entry_id, url)| async move { (entry_id, extract_content(client, url).await) })
+            .collect::<Vec<_>>();
+        let extracted = stream::iter(tasks)
+            .buffer_unordered(MAX_CONCURRENT_FETCHES)
+            .collect::<Vec<_>>()
+            .await;
+        articles = extracted
+            .into_iter()
+            .filter_map(|(entry_id, article)| Some((entry_id, article?)))
+            .collect();
+    }
+
+    for (mut entry, item) in entries {
+        let mut extras = EntryExtras::from_item(&entry, item);
+        if let Some(article) = articles.get(&entry.id) {
+            extras.original.content = entry.content;
+            entry.content = Some(&article.content);
+            store.add_article(entry.id, &ExtractedArticle::from_article(article))?;
+        }
+        store.insert_entry(entry, extras)?;
+    }
     Ok(())
 }
+
+async fn extract_content(client: &HttpClient, url: &str) -> Option<Article> {
+    match fetch_article(client, url).await {
+        Ok(article) => article,
+        Err(err) => {
+            tracing::error!("failed to extract the content of {url}: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::body::Bytes;
+
+    use super::*;
+    use crate::types::Account;
+
+    fn podcast_page() -> Page {
+        Page {
+            url: "https://podcast.example.com/feed.xml".parse().unwrap(),
+            content_type: Some("application/rss+xml".to_owned()),
+            body: Bytes::from_static(include_bytes!("../tests/fixtures/podcast.xml")),
+        }
+    }
+
+    #[tokio::test]
+    async fn podcast_enclosures_are_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(Store::new(dir.path()).unwrap());
+        store
+            .set_account(&Account {
+                name: "alice",
+                password_hash: "",
+                fever_key: "",
+                reader_token: "",
+                disabled: false,
+                created_at: OffsetDateTime::now_utc(),
+            })
+            .unwrap();
+        let repo = store.get_repo("alice").unwrap().unwrap();
+
+        let page = podcast_page();
+        let feed = parse_feed(&page).unwrap();
+        let feed_id = store.new_feed_id().unwrap();
+        let created_at = OffsetDateTime::now_utc();
+        let url = "https://podcast.example.com/feed.xml";
+        repo.add_subscription(&Subscription::from_feed(feed_id, &feed, url, created_at))
+            .unwrap();
+        refresh_feed(&store, feed_id, &feed).await.unwrap();
+
+        let mut enclosures = vec![];
+        for item in &feed.channel.items {
+            let entry = Entry::from_item(feed_id, item, created_at).unwrap();
+            let extras = repo.get_entry_extras(entry.id).unwrap().unwrap();
+            let extras = extras.value().unwrap();
+            let enclosure = extras.enclosure.unwrap();
+            enclosures.push((
+                enclosure.enclosure_url.to_owned(),
+                enclosure.enclosure_type.map(str::to_owned),
+                enclosure.size(),
+            ));
+        }
+        assert_eq!(enclosures, vec![
+            (
+                "https://cdn.example.com/audio/episode-42.mp3".to_owned(),
+                Some("audio/mpeg".to_owned()),
+                48213711
+            ),
+            (
+                "https://cdn.example.com/video/episode-41.mp4".to_owned(),
+                Some("video/mp4".to_owned()),
+                104857600
+            ),
+        ]);
+    }
+}
@@ -1,216 +1,1969 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::{Bound, RangeBounds};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
+use hyper::Uri;
+use serde::{Deserialize, Serialize};
 use sled_bincode::{Batch, Db, Error as SledBinError, Key, Transactional, Tree, TreeEntry, Value};
+use time::OffsetDateTime;
 
 use crate::result::Result;
-use crate::types::{Entry, EntryId, FeedId, Subscription, Tagging, TaggingId};
+use crate::search::{self, Document, SearchFilter, SearchQuery};
+use crate::types::{
+    Account, ApiToken, Entry, EntryExtras, EntryId, ExtractedArticle, FeedId, FeedOptions, FeedSource, Icon, SavedSearch, SavedSearchId, Subscription, Tag, Tagging, TaggingId
+};
 
-pub struct Repo {
+const MAX_RECENTLY_READ: usize = 100;
+// bumped whenever stored data has to be migrated on startup
+const SCHEMA_VERSION: u32 = 8;
+// the ID the pages pseudo-feed had before it was changed to one that clients can decode
+const LEGACY_PAGES_FEED_ID: FeedId = FeedId(u64::MAX);
+
+// storage shared by all users, feeds are fetched once and their entries are stored once
+pub struct Store {
     db: Db,
-    subs: Tree<SubscriptionEntry>,
-    unread: Tree<MarkedEntry>,
-    starred: Tree<MarkedEntry>,
     entries: Tree<FeedEntry>,
-    taggings: Tree<TaggingEntry>,
+    entry_seqs: Tree<EntrySeqEntry>,
+    // number of stored entries per feed, entries are never removed so it only grows
+    entry_counts: Tree<EntryCountEntry>,
+    seq_index: Tree<SeqIndexEntry>,
+    search_index: Tree<SearchIndexEntry>,
+    subscribers: Tree<SubscriberEntry>,
+    extras: Tree<ExtrasEntry>,
+    icons: Tree<IconEntry>,
+    articles: Tree<ArticleEntry>,
+    users: Tree<UserEntry>,
+    accounts: Tree<AccountEntry>,
+    api_tokens: Tree<ApiTokenEntry>,
+    admin_passwords: Tree<AdminPasswordEntry>,
+    meta: Tree<MetaEntry>,
+    user_trees: Mutex<HashMap<String, Arc<UserTrees>>>,
+    // held while an entry is numbered, see `insert_new_entry`
+    seq_lock: Mutex<()>,
 }
 
-impl Repo {
+impl Store {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let db = sled_bincode::open(path)?;
 
-        let repo = Repo {
-            subs: Tree::open(&db, "subs").unwrap(),
-            unread: Tree::open(&db, "unread").unwrap(),
-            starred: Tree::open(&db, "starred").unwrap(),
+        let store = Store {
             entries: Tree::open(&db, "entries").unwrap(),
-            taggings: Tree::open(&db, "taggings").unwrap(),
+            entry_seqs: Tree::open(&db, "entry_seqs").unwrap(),
+            entry_counts: Tree::open(&db, "entry_counts").unwrap(),
+            seq_index: Tree::open(&db, "seq_index").unwrap(),
+            search_index: Tree::open(&db, "search_terms").unwrap(),
+            subscribers: Tree::open(&db, "subscribers").unwrap(),
+            extras: Tree::open(&db, "extras").unwrap(),
+            icons: Tree::open(&db, "icons").unwrap(),
+            articles: Tree::open(&db, "articles").unwrap(),
+            users: Tree::open(&db, "users").unwrap(),
+            accounts: Tree::open(&db, "accounts").unwrap(),
+            api_tokens: Tree::open(&db, "api_tokens").unwrap(),
+            admin_passwords: Tree::open(&db, "admin_passwords").unwrap(),
+            meta: Tree::open(&db, "meta").unwrap(),
+            user_trees: Mutex::default(),
+            seq_lock: Mutex::default(),
             db,
         };
-        Ok(repo)
+        store.migrate()?;
+        Ok(store)
     }
 
-    pub fn get_unread(&self) -> Result<Vec<Key<MarkedEntry>>> {
-        Ok(self.unread.iter().keys().collect::<Result<_, SledBinError>>()?)
+    // brings data stored by earlier versions up to date, every step only runs once
+    fn migrate(&self) -> Result<()> {
+        let version = match self.meta.get(&"version")? {
+            Some(res) => res.value()?,
+            None => 0,
+        };
+        if version < 1 {
+            for trees in self.open_all_user_trees()? {
+                trees.reindex_taggings()?;
+            }
+        }
+        if version < 2 {
+            // the index used to store a single list of entries per term
+            self.db.drop_tree("search_index")?;
+            self.rebuild_search_index()?;
+        }
+        if version < 3 {
+            self.assign_entry_seqs()?;
+        }
+        if version < 4 {
+            for trees in self.open_all_user_trees()? {
+                for sub in trees.subs.iter().values() {
+                    let feed_id = sub?.value()?.feed_id;
+                    self.subscribers.insert(&SubscriberKey::new(feed_id, &trees.name), &())?;
+                }
+                trees.index_pages(&self.entries)?;
+            }
+        }
+        if version < 5 {
+            // Fever keys used to be derived from the password of the account
+            for res in self.get_accounts()? {
+                let account = res.value()?;
+                self.set_account(&Account {
+                    fever_key: "",
+                    ..account
+                })?;
+            }
+        }
+        if version < 6 {
+            self.move_pages_feed()?;
+        }
+        if version < 7 {
+            self.count_stored_entries()?;
+        }
+        if version < 8 {
+            for trees in self.open_all_user_trees()? {
+                trees.index_modified(&self.entries)?;
+            }
+        }
+        if version < SCHEMA_VERSION {
+            self.meta.insert(&"version", &SCHEMA_VERSION)?;
+        }
+        Ok(())
     }
 
-    pub fn add_unread<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
+    fn move_pages_feed(&self) -> Result<()> {
         let mut batch = Batch::default();
-        for entry in entries {
-            batch.insert(&entry, &())?;
+        for res in self.entries.iter().values() {
+            let res = res?;
+            let entry = res.value()?;
+            if entry.feed_id == LEGACY_PAGES_FEED_ID {
+                batch.insert(&entry.id, &Entry {
+                    feed_id: FeedId::PAGES,
+                    ..entry
+                })?;
+            }
+        }
+        self.entries.apply_batch(batch)?;
+
+        for trees in self.open_all_user_trees()? {
+            if trees.move_pages_feed()? {
+                self.subscribers.remove(&SubscriberKey::new(LEGACY_PAGES_FEED_ID, &trees.name))?;
+                self.subscribers.insert(&SubscriberKey::new(FeedId::PAGES, &trees.name), &())?;
+            }
         }
-        self.unread.apply_batch(batch)?;
         Ok(())
     }
 
-    pub fn delete_unread<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
-        let mut batch = Batch::default();
-        for entry in entries {
-            batch.remove(&entry)?;
+    fn open_all_user_trees(&self) -> Result<Vec<UserTrees>> {
+        let mut res = vec![];
+        for name in self.get_users()? {
+            if let Some(prefix) = self.users.get(&name.as_str())? {
+                res.push(UserTrees::open(&self.db, &name, prefix.value()?)?);
+            }
         }
-        self.unread.apply_batch(batch)?;
-        Ok(())
+        Ok(res)
     }
 
-    pub fn get_starred(&self) -> Result<Vec<Key<MarkedEntry>>> {
-        Ok(self.starred.iter().keys().collect::<Result<_, SledBinError>>()?)
+    pub fn get_users(&self) -> Result<Vec<String>> {
+        let mut res = vec![];
+        for key in self.users.iter().keys() {
+            res.push(key?.key()?.to_owned());
+        }
+        Ok(res)
     }
 
-    pub fn add_starred<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
-        let mut batch = Batch::default();
-        for entry in entries {
-            batch.insert(&entry, &())?;
+    fn add_user(&self, name: &str) -> Result<()> {
+        if self.users.get(&name)?.is_some() {
+            return Ok(());
         }
-        self.starred.apply_batch(batch)?;
+        // the first user inherits the trees that were stored before multi-user support,
+        // the prefixes of other users are unique, so that a deleted user's data is never reused
+        let prefix = if self.users.iter().keys().next().is_none() {
+            String::new()
+        } else {
+            format!("users/{}/", self.db.generate_id()?)
+        };
+        self.users.insert(&name, &prefix.as_str())?;
         Ok(())
     }
 
-    pub fn delete_starred<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
-        let mut batch = Batch::default();
-        for entry in entries {
-            batch.remove(&entry)?;
+    // removes the account along with all of the user's data
+    pub fn delete_user(&self, name: &str) -> Result<bool> {
+        let prefix = match self.users.get(&name)? {
+            Some(res) => res.value()?.to_owned(),
+            None => return Ok(false),
+        };
+        let trees = self.user_trees.lock().unwrap().remove(name);
+        let trees = match trees {
+            Some(trees) => trees,
+            None => Arc::new(UserTrees::open(&self.db, name, &prefix)?),
+        };
+        for sub in trees.subs.iter().values() {
+            let feed_id = sub?.value()?.feed_id;
+            self.subscribers.remove(&SubscriberKey::new(feed_id, name))?;
         }
-        self.starred.apply_batch(batch)?;
+        trees.clear()?;
+        for (id, _) in self.get_api_tokens(name)? {
+            self.api_tokens.remove(&id.as_str())?;
+        }
+        self.admin_passwords.remove(&name)?;
+        self.accounts.remove(&name)?;
+        self.users.remove(&name)?;
+        Ok(true)
+    }
+
+    pub fn get_accounts(&self) -> Result<Vec<Value<AccountEntry>>> {
+        Ok(self
+            .accounts
+            .iter()
+            .values()
+            .collect::<Result<_, SledBinError>>()?)
+    }
+
+    pub fn get_account(&self, name: &str) -> Result<Option<Value<AccountEntry>>> {
+        Ok(self.accounts.get(&name)?)
+    }
+
+    // creates the user when the account is new
+    pub fn set_account(&self, account: &Account) -> Result<()> {
+        self.add_user(account.name)?;
+        self.accounts.insert(&account.name, account)?;
         Ok(())
     }
 
-    pub fn get_entries(
-        &self,
-        page: usize,
-        per_page: usize,
-        tags: &[String],
-    ) -> Result<Vec<Value<FeedEntry>>> {
-        let res = if !tags.is_empty() {
-            let feeds = self.get_feeds_by_tags(tags)?;
-            let filter_by_feeds = |res: &Value<FeedEntry>| -> bool {
-                matches!(res.value(), Ok(entry) if feeds.contains(&entry.feed_id))
-            };
+    // admin passwords are kept apart from the accounts, so that the credentials used by reader
+    // clients don't grant access to administrative actions
+    pub fn get_admin_password(&self, name: &str) -> Result<Option<Value<AdminPasswordEntry>>> {
+        Ok(self.admin_passwords.get(&name)?)
+    }
 
-            self.entries
-                .iter()
-                .values()
-                .rev()
-                .filter(|res| res.as_ref().map_or(false, filter_by_feeds))
-                .skip(per_page * (page.max(1) - 1))
-                .take(per_page)
-                .collect::<Result<Vec<_>, _>>()?
-        } else {
-            self.entries
-                .iter()
-                .values()
-                .rev()
-                .skip(per_page * (page.max(1) - 1))
-                .take(per_page)
-                .collect::<Result<Vec<_>, _>>()?
+    pub fn set_admin_password(&self, name: &str, password_hash: &str) -> Result<()> {
+        self.admin_passwords.insert(&name, &password_hash)?;
+        Ok(())
+    }
+
+    pub fn delete_admin_password(&self, name: &str) -> Result<bool> {
+        Ok(self.admin_passwords.remove(&name)?.is_some())
+    }
+
+    // tokens are stored under the SHA-256 hash of the secret, which also serves as their ID
+    pub fn get_api_tokens(&self, user: &str) -> Result<Vec<(String, Value<ApiTokenEntry>)>> {
+        let mut res = vec![];
+        for key in self.api_tokens.iter().keys() {
+            let id = key?.key()?.to_owned();
+            if let Some(token) = self.api_tokens.get(&id.as_str())? {
+                if token.value()?.user == user {
+                    res.push((id, token));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_api_token(&self, id: &str) -> Result<Option<Value<ApiTokenEntry>>> {
+        Ok(self.api_tokens.get(&id)?)
+    }
+
+    pub fn add_api_token(&self, id: &str, token: &ApiToken) -> Result<()> {
+        self.api_tokens.insert(&id, token)?;
+        Ok(())
+    }
+
+    pub fn delete_api_token(&self, id: &str) -> Result<bool> {
+        Ok(self.api_tokens.remove(&id)?.is_some())
+    }
+
+    // returns `None` when the user does not exist
+    pub fn get_repo(self: &Arc<Self>, name: &str) -> Result<Option<Arc<Repo>>> {
+        let mut cache = self.user_trees.lock().unwrap();
+        if let Some(trees) = cache.get(name) {
+            return Ok(Some(Arc::new(Repo::new(self.clone(), trees.clone()))));
+        }
+        let prefix = match self.users.get(&name)? {
+            Some(res) => res.value()?.to_owned(),
+            None => return Ok(None),
         };
+        let trees = Arc::new(UserTrees::open(&self.db, name, &prefix)?);
+        cache.insert(name.to_owned(), trees.clone());
+        Ok(Some(Arc::new(Repo::new(self.clone(), trees))))
+    }
+
+    // names of the users subscribed to a feed
+    fn get_subscribers(&self, feed_id: FeedId) -> Result<Vec<String>> {
+        let mut res = vec![];
+        for key in self.subscribers.range(SubscriberKey::new(feed_id, "")..).keys() {
+            let key = key?;
+            let key = key.key()?;
+            if key.feed_id != feed_id {
+                break;
+            }
+            res.push(key.user.to_owned());
+        }
         Ok(res)
     }
 
-    pub fn get_starred_entries(&self, page: usize, per_page: usize) -> Result<Vec<Value<FeedEntry>>> {
-        let res = self
-            .starred
-            .iter()
-            .keys()
-            .rev()
-            .skip(per_page * (page.max(1) - 1))
-            .take(per_page)
-            .map(|res| self.entries.get(&res?.key()?))
-            .filter_map(Result::transpose)
-            .collect::<Result<Vec<_>, _>>()?;
+    pub fn get_repos(self: &Arc<Self>) -> Result<Vec<Arc<Repo>>> {
+        let mut res = vec![];
+        for name in self.get_users()? {
+            res.extend(self.get_repo(&name)?);
+        }
         Ok(res)
     }
 
-    pub fn get_subscriptions(&self) -> Result<Vec<Value<SubscriptionEntry>>> {
-        Ok(self.subs.iter().values().collect::<Result<_, SledBinError>>()?)
+    // subscriptions of all users, with a single subscription per feed
+    pub fn get_subscriptions(self: &Arc<Self>) -> Result<Vec<Value<SubscriptionEntry>>> {
+        let mut feeds = HashSet::new();
+        let mut res = vec![];
+        for repo in self.get_repos()? {
+            for sub in repo.get_subscriptions()? {
+                if feeds.insert(sub.value()?.feed_id) {
+                    res.push(sub);
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    // returns the ID of a feed any user is subscribed to
+    pub fn get_feed_id(self: &Arc<Self>, feed_url: &str) -> Result<Option<FeedId>> {
+        for repo in self.get_repos()? {
+            if let Some(sub) = repo.get_subscription_by_url(feed_url)? {
+                return Ok(Some(sub.value()?.feed_id));
+            }
+        }
+        Ok(None)
     }
 
     pub fn new_feed_id(&self) -> Result<FeedId> {
         Ok(FeedId(self.db.generate_id()?))
     }
 
-    pub fn add_subscription(&self, sub: &Subscription) -> Result<()> {
-        self.subs.insert(&sub.feed_id, sub)?;
-        Ok(())
+    // content is extracted when any of the subscribers has asked for it
+    pub fn get_feed_options(self: &Arc<Self>, id: FeedId) -> Result<FeedOptions> {
+        let mut res = FeedOptions::default();
+        for name in self.get_subscribers(id)? {
+            if let Some(repo) = self.get_repo(&name)? {
+                res.extract_content |= repo.get_feed_options(id)?.extract_content;
+            }
+        }
+        Ok(res)
     }
 
-    pub fn delete_subscription(&self, id: FeedId) -> Result<()> {
-        self.subs.remove(&id)?;
+    // new entries are marked as unread for every user subscribed to the feed
+    pub fn insert_entry(self: &Arc<Self>, entry: Entry, extras: EntryExtras) -> Result<()> {
+        let id = entry.id;
+        let feed_id = entry.feed_id;
+        if !self.insert_new_entry(entry, extras)? {
+            return Ok(());
+        }
+        for name in self.get_subscribers(feed_id)? {
+            if let Some(repo) = self.get_repo(&name)? {
+                repo.trees.unread.insert(&id, &())?;
+            }
+        }
         Ok(())
     }
 
-    pub fn insert_entry(&self, entry: Entry) -> Result<()> {
-        (&self.entries, &self.unread).transaction(|entries, unread| {
-            if entries.insert(&entry.id, &entry)?.is_none() {
-                unread.insert(&entry.id, &())?;
+    // returns `false` when the entry is already stored, stored entries are left as they are,
+    // because their content might have been replaced with an extracted article
+    fn insert_new_entry(&self, mut entry: Entry, extras: EntryExtras) -> Result<bool> {
+        let terms = search::entry_terms(&entry);
+        // entries are created and numbered in the same order, so that clients can stop
+        // at the first entry created before their last update when going through them
+        let _lock = self.seq_lock.lock().unwrap();
+        entry.created_at = OffsetDateTime::now_utc();
+        let is_new = (&self.entries, &self.search_index).transaction(|entries, index| {
+            if entries.get(&entry.id)?.is_some() {
+                return Ok(false);
             }
-            Ok(())
+            entries.insert(&entry.id, &entry)?;
+            for term in &terms {
+                index.insert(&SearchTermKey::new(term, entry.id), &())?;
+            }
+            Ok(true)
         })?;
+        if is_new {
+            self.extras.insert(&entry.id, &extras)?;
+            self.add_entry_seq(entry.id)?;
+            let count = self.get_entry_count(entry.feed_id)?;
+            self.entry_counts.insert(&entry.feed_id, &(count + 1))?;
+        }
+        Ok(is_new)
+    }
+
+    fn get_entry_count(&self, feed_id: FeedId) -> Result<u64> {
+        match self.entry_counts.get(&feed_id)? {
+            Some(res) => Ok(res.value()?),
+            None => Ok(0),
+        }
+    }
+
+    // entries stored before they were counted
+    fn count_stored_entries(&self) -> Result<()> {
+        let mut counts: HashMap<FeedId, u64> = HashMap::new();
+        for res in self.entries.iter().values() {
+            let res = res?;
+            *counts.entry(res.value()?.feed_id).or_default() += 1;
+        }
+        let mut batch = Batch::default();
+        for (feed_id, count) in counts {
+            batch.insert(&feed_id, &count)?;
+        }
+        self.entry_counts.apply_batch(batch)?;
         Ok(())
     }
 
-    pub fn get_taggings(&self) -> Result<Vec<Value<TaggingEntry>>> {
-        let res = self
-            .taggings
-            .iter()
-            .values()
-            .collect::<Result<_, SledBinError>>()?;
+    // entries are numbered in the order they're stored in, unlike entry IDs the numbers only grow,
+    // they start at 1 because clients treat 0 as no ID
+    fn add_entry_seq(&self, id: EntryId) -> Result<()> {
+        let seq = self.db.generate_id()? + 1;
+        self.seq_index.insert(&EntrySeqKey::new(seq, id), &())?;
+        self.entry_seqs.insert(&id, &seq)?;
+        Ok(())
+    }
+
+    // entries stored before sequence numbers were introduced are numbered in the order they were created in
+    fn assign_entry_seqs(&self) -> Result<()> {
+        let mut ids = vec![];
+        for res in self.entries.iter().values() {
+            let res = res?;
+            let entry = res.value()?;
+            if self.entry_seqs.get(&entry.id)?.is_none() {
+                ids.push((entry.created_at, entry.id));
+            }
+        }
+        ids.sort_unstable();
+        for (_, id) in ids {
+            self.add_entry_seq(id)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_entry(&self, id: EntryId) -> Result<Option<Value<FeedEntry>>> {
+        Ok(self.entries.get(&id)?)
+    }
+
+    pub fn has_entry(&self, id: EntryId) -> Result<bool> {
+        Ok(self.entries.get(&id)?.is_some())
+    }
+
+    // entries stored before the full-text index was introduced need to be indexed
+    fn rebuild_search_index(&self) -> Result<()> {
+        let mut batch = Batch::default();
+        for res in self.entries.iter().values() {
+            let res = res?;
+            let entry = res.value()?;
+            for term in search::entry_terms(&entry) {
+                batch.insert(&SearchTermKey::new(&term, entry.id), &())?;
+            }
+        }
+        self.search_index.apply_batch(batch)?;
+        Ok(())
+    }
+
+    // returns the entries indexed under a term
+    fn get_term_entries(&self, term: &str) -> Result<HashSet<EntryId>> {
+        let range = SearchTermKey::new(term, EntryId(u64::MIN))..=SearchTermKey::new(term, EntryId(u64::MAX));
+        let mut res = HashSet::new();
+        for key in self.search_index.range(range).keys() {
+            res.insert(key?.key()?.entry_id);
+        }
         Ok(res)
     }
 
-    pub fn new_tagging_id(&self) -> Result<TaggingId> {
-        Ok(TaggingId(self.db.generate_id()?))
+    // returns the user a shared feed belongs to along with its source
+    pub fn get_shared_feed(self: &Arc<Self>, token: &str) -> Result<Option<(Arc<Repo>, FeedSource)>> {
+        for repo in self.get_repos()? {
+            if let Some(source) = repo.get_shared_feed(token)? {
+                return Ok(Some((repo, source)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn get_article(&self, id: EntryId) -> Result<Option<Value<ArticleEntry>>> {
+        Ok(self.articles.get(&id)?)
     }
 
-    pub fn add_tagging(&self, tagging: &Tagging) -> Result<()> {
-        self.taggings.insert(&tagging.id, tagging)?;
+    pub fn add_article(&self, id: EntryId, article: &ExtractedArticle) -> Result<()> {
+        self.articles.insert(&id, article)?;
         Ok(())
     }
 
-    pub fn delete_tagging(&self, id: TaggingId) -> Result<()> {
-        self.taggings.remove(&id)?;
+    pub fn get_icon(&self, host: &str) -> Result<Option<Value<IconEntry>>> {
+        Ok(self.icons.get(&host)?)
+    }
+
+    pub fn add_icon(&self, host: &str, icon: &Icon) -> Result<()> {
+        self.icons.insert(&host, icon)?;
         Ok(())
     }
+}
 
-    fn get_feeds_by_tags(&self, tags: &[String]) -> Result<Vec<FeedId>> {
-        let mut feeds = vec![];
+struct UserTrees {
+    name: String,
+    id: u64,
+    subs: Tree<SubscriptionEntry>,
+    unread: Tree<MarkedEntry>,
+    starred: Tree<MarkedEntry>,
+    pages: Tree<MarkedEntry>,
+    taggings: Tree<TaggingEntry>,
+    tagging_index: Tree<TaggingIndexEntry>,
+    saved_searches: Tree<SavedSearchEntry>,
+    recently_read: Tree<RecentlyReadEntry>,
+    feed_options: Tree<FeedOptionsEntry>,
+    modified: Tree<ModifiedEntry>,
+    modified_index: Tree<ModifiedIndexEntry>,
+    folders: Tree<FolderEntry>,
+    shared_feeds: Tree<SharedFeedEntry>,
+}
+
+impl UserTrees {
+    fn clear(&self) -> Result<()> {
+        clear_tree(&self.subs)?;
+        clear_tree(&self.unread)?;
+        clear_tree(&self.starred)?;
+        clear_tree(&self.pages)?;
+        clear_tree(&self.taggings)?;
+        clear_tree(&self.tagging_index)?;
+        clear_tree(&self.saved_searches)?;
+        clear_tree(&self.recently_read)?;
+        clear_tree(&self.feed_options)?;
+        clear_tree(&self.modified)?;
+        clear_tree(&self.modified_index)?;
+        clear_tree(&self.folders)?;
+        clear_tree(&self.shared_feeds)
+    }
+
+    // taggings created before the index was introduced need to be indexed, duplicates that
+    // could be created back then are removed
+    fn reindex_taggings(&self) -> Result<()> {
+        let mut indexed = HashSet::new();
+        let mut taggings = Batch::default();
+        let mut index = Batch::default();
         for tagging in self.taggings.iter().values() {
             let tagging = tagging?;
             let tagging = tagging.value()?;
-            if tags.iter().any(|str| str == tagging.name) {
-                feeds.push(tagging.feed_id);
+            if indexed.insert((tagging.feed_id, tagging.name.to_owned())) {
+                index.insert(&TaggingKey::new(tagging.feed_id, tagging.name), &tagging.id)?;
+            } else {
+                taggings.remove(&tagging.id)?;
             }
         }
-        Ok(feeds)
+        clear_tree(&self.tagging_index)?;
+        self.tagging_index.apply_batch(index)?;
+        self.taggings.apply_batch(taggings)?;
+        Ok(())
     }
-}
 
-#[derive(Debug, Default)]
-pub struct SubscriptionEntry;
+    // returns `false` when the user has no pages subscription under the legacy ID
+    fn move_pages_feed(&self) -> Result<bool> {
+        let res = match self.subs.remove(&LEGACY_PAGES_FEED_ID)? {
+            Some(res) => res,
+            None => return Ok(false),
+        };
+        self.subs.insert(&FeedId::PAGES, &Subscription {
+            id: FeedId::PAGES,
+            feed_id: FeedId::PAGES,
+            ..res.value()?
+        })?;
+        if let Some(res) = self.feed_options.remove(&LEGACY_PAGES_FEED_ID)? {
+            self.feed_options.insert(&FeedId::PAGES, &res.value()?)?;
+        }
+        let mut batch = Batch::default();
+        for res in self.taggings.iter().values() {
+            let res = res?;
+            let tagging = res.value()?;
+            if tagging.feed_id == LEGACY_PAGES_FEED_ID {
+                batch.insert(&tagging.id, &Tagging {
+                    feed_id: FeedId::PAGES,
+                    ..tagging
+                })?;
+            }
+        }
+        self.taggings.apply_batch(batch)?;
+        self.reindex_taggings()?;
+        Ok(true)
+    }
 
-impl<'a> TreeEntry<'a> for SubscriptionEntry {
-    type Key = FeedId;
-    type Val = Subscription<'a>;
-}
+    // pages saved before multi-user support belong to the user that inherited the stored data
+    fn index_pages(&self, entries: &Tree<FeedEntry>) -> Result<()> {
+        if self.subs.get(&FeedId::PAGES)?.is_none() || self.pages.iter().keys().next().is_some() {
+            return Ok(());
+        }
+        let mut batch = Batch::default();
+        for res in entries.iter().values() {
+            let res = res?;
+            let entry = res.value()?;
+            if entry.feed_id == FeedId::PAGES {
+                batch.insert(&entry.id, &())?;
+            }
+        }
+        self.pages.apply_batch(batch)?;
+        Ok(())
+    }
 
-#[derive(Debug, Default)]
-pub struct MarkedEntry;
+    // modification times stored before they were indexed, entries the user can no longer read are dropped
+    fn index_modified(&self, entries: &Tree<FeedEntry>) -> Result<()> {
+        let mut modified = Batch::default();
+        let mut index = Batch::default();
+        for key in self.modified.iter().keys() {
+            let entry_id = key?.key()?;
+            let readable = match entries.get(&entry_id)? {
+                Some(res) if res.value()?.feed_id == FeedId::PAGES => self.pages.get(&entry_id)?.is_some(),
+                Some(res) => self.subs.get(&res.value()?.feed_id)?.is_some(),
+                None => false,
+            };
+            match self.modified.get(&entry_id)? {
+                Some(res) if readable => index.insert(&ModifiedKey::new(res.value()?, entry_id), &())?,
+                _ => modified.remove(&entry_id)?,
+            }
+        }
+        clear_tree(&self.modified_index)?;
+        self.modified_index.apply_batch(index)?;
+        self.modified.apply_batch(modified)?;
+        Ok(())
+    }
 
-impl<'a> TreeEntry<'a> for MarkedEntry {
-    type Key = EntryId;
-    type Val = ();
+    fn open(db: &Db, name: &str, prefix: &str) -> Result<Self> {
+        // the first user owns the unprefixed trees, the others are numbered by their unique prefix
+        let id = prefix
+            .strip_prefix("users/")
+            .and_then(|rest| rest.strip_suffix('/'))
+            .and_then(|id| id.parse::<u64>().ok())
+            .map_or(1, |id| id + 2);
+        Ok(UserTrees {
+            name: name.to_owned(),
+            id,
+            subs: Tree::open(db, &format!("{prefix}subs"))?,
+            unread: Tree::open(db, &format!("{prefix}unread"))?,
+            starred: Tree::open(db, &format!("{prefix}starred"))?,
+            pages: Tree::open(db, &format!("{prefix}pages"))?,
+            taggings: Tree::open(db, &format!("{prefix}taggings"))?,
+            tagging_index: Tree::open(db, &format!("{prefix}tagging_index"))?,
+            saved_searches: Tree::open(db, &format!("{prefix}saved_searches"))?,
+            recently_read: Tree::open(db, &format!("{prefix}recently_read"))?,
+            feed_options: Tree::open(db, &format!("{prefix}feed_options"))?,
+            modified: Tree::open(db, &format!("{prefix}modified"))?,
+            modified_index: Tree::open(db, &format!("{prefix}modified_index"))?,
+            folders: Tree::open(db, &format!("{prefix}folders"))?,
+            shared_feeds: Tree::open(db, &format!("{prefix}shared_feeds"))?,
+        })
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct FeedEntry;
-
-impl<'a> TreeEntry<'a> for FeedEntry {
-    type Key = EntryId;
-    type Val = Entry<'a>;
+// the data of a single user, entries are only visible when the user is subscribed
+// to their feed or has saved them as a page
+pub struct Repo {
+    store: Arc<Store>,
+    trees: Arc<UserTrees>,
 }
 
-#[derive(Debug, Default)]
-pub struct TaggingEntry;
+impl Repo {
+    fn new(store: Arc<Store>, trees: Arc<UserTrees>) -> Self {
+        Self { store, trees }
+    }
 
-impl<'a> TreeEntry<'a> for TaggingEntry {
-    type Key = TaggingId;
-    type Val = Tagging<'a>;
+    pub fn store(&self) -> &Arc<Store> {
+        &self.store
+    }
+
+    pub fn user_name(&self) -> &str {
+        &self.trees.name
+    }
+
+    // a numeric id that stays the same for the lifetime of the account and is never shared
+    pub fn user_id(&self) -> u64 {
+        self.trees.id
+    }
+
+    pub fn get_unread(&self) -> Result<Vec<Key<MarkedEntry>>> {
+        Ok(self
+            .trees
+            .unread
+            .iter()
+            .keys()
+            .collect::<Result<_, SledBinError>>()?)
+    }
+
+    // returns the entries that were marked, entries the user can't read are skipped
+    pub fn add_unread<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<Vec<EntryId>> {
+        let ids = self.get_readable_ids(entries)?;
+        let mut batch = Batch::default();
+        for entry in &ids {
+            batch.insert(entry, &())?;
+        }
+        self.trees.unread.apply_batch(batch)?;
+        self.touch_entries(ids.iter().copied())?;
+        Ok(ids)
+    }
+
+    pub fn delete_unread<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
+        let mut batch = Batch::default();
+        let mut read = vec![];
+        for entry in entries {
+            if self.trees.unread.get(&entry)?.is_some() {
+                read.push(entry);
+            }
+            batch.remove(&entry)?;
+        }
+        self.trees.unread.apply_batch(batch)?;
+        self.touch_entries(read.iter().copied())?;
+        self.add_recently_read(read)?;
+        Ok(())
+    }
+
+    pub fn get_recently_read(&self) -> Result<Vec<EntryId>> {
+        let mut res = vec![];
+        for key in self.trees.recently_read.iter().keys() {
+            let id = key?.key()?;
+            if let Some(read_at) = self.trees.recently_read.get(&id)? {
+                res.push((read_at.value()?, id));
+            }
+        }
+        res.sort_by_key(|(read_at, _)| std::cmp::Reverse(*read_at));
+        Ok(res.into_iter().map(|(_, id)| id).collect())
+    }
+
+    pub fn add_recently_read<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<Vec<EntryId>> {
+        let ids = self.get_readable_ids(entries)?;
+        let read_at = OffsetDateTime::now_utc().unix_timestamp();
+        let mut batch = Batch::default();
+        for entry in &ids {
+            batch.insert(entry, &read_at)?;
+        }
+        self.trees.recently_read.apply_batch(batch)?;
+
+        // only the most recent entries are kept
+        let mut batch = Batch::default();
+        for entry in self.get_recently_read()?.iter().skip(MAX_RECENTLY_READ) {
+            batch.remove(entry)?;
+        }
+        self.trees.recently_read.apply_batch(batch)?;
+        Ok(ids)
+    }
+
+    pub fn get_starred(&self) -> Result<Vec<Key<MarkedEntry>>> {
+        Ok(self
+            .trees
+            .starred
+            .iter()
+            .keys()
+            .collect::<Result<_, SledBinError>>()?)
+    }
+
+    // returns the entries that were starred, entries the user can't read are skipped
+    pub fn add_starred<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<Vec<EntryId>> {
+        let ids = self.get_readable_ids(entries)?;
+        let mut batch = Batch::default();
+        for entry in &ids {
+            batch.insert(entry, &())?;
+        }
+        self.trees.starred.apply_batch(batch)?;
+        self.touch_entries(ids.iter().copied())?;
+        Ok(ids)
+    }
+
+    pub fn delete_starred<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
+        let mut batch = Batch::default();
+        let mut unstarred = vec![];
+        for entry in entries {
+            if self.trees.starred.get(&entry)?.is_some() {
+                unstarred.push(entry);
+            }
+            batch.remove(&entry)?;
+        }
+        self.trees.starred.apply_batch(batch)?;
+        self.touch_entries(unstarred)
+    }
+
+    // IDs are easy to guess, so the ones supplied by clients are checked before they're stored
+    fn get_readable_ids<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<Vec<EntryId>> {
+        let mut res = vec![];
+        for id in entries {
+            if self.get_entry(id)?.is_some() {
+                res.push(id);
+            }
+        }
+        Ok(res)
+    }
+
+    // returns the last time the read or starred state of an entry changed
+    pub fn get_modified(&self, id: EntryId) -> Result<Option<i64>> {
+        match self.trees.modified.get(&id)? {
+            Some(res) => Ok(Some(res.value()?)),
+            None => Ok(None),
+        }
+    }
+
+    // returns the entries that changed after the given time, the oldest changes first
+    pub fn get_modified_since(&self, since: i64) -> Result<Vec<EntryId>> {
+        let start = ModifiedKey::new(since, EntryId(u64::MAX));
+        let mut res = vec![];
+        for key in self
+            .trees
+            .modified_index
+            .range((Bound::Excluded(start), Bound::Unbounded))
+            .keys()
+        {
+            res.push(key?.key()?.entry_id);
+        }
+        Ok(res)
+    }
+
+    fn touch_entries<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
+        let modified = OffsetDateTime::now_utc().unix_timestamp();
+        let mut batch = Batch::default();
+        let mut index = Batch::default();
+        for entry in entries {
+            if let Some(previous) = self.get_modified(entry)? {
+                index.remove(&ModifiedKey::new(previous, entry))?;
+            }
+            batch.insert(&entry, &modified)?;
+            index.insert(&ModifiedKey::new(modified, entry), &())?;
+        }
+        self.trees.modified.apply_batch(batch)?;
+        self.trees.modified_index.apply_batch(index)?;
+        Ok(())
+    }
+
+    // drops the modification times of entries the user can no longer read
+    fn forget_modified<I: IntoIterator<Item = EntryId>>(&self, entries: I) -> Result<()> {
+        let mut batch = Batch::default();
+        let mut index = Batch::default();
+        for entry in entries {
+            if let Some(previous) = self.get_modified(entry)? {
+                index.remove(&ModifiedKey::new(previous, entry))?;
+            }
+            batch.remove(&entry)?;
+        }
+        self.trees.modified.apply_batch(batch)?;
+        self.trees.modified_index.apply_batch(index)?;
+        Ok(())
+    }
+
+    pub fn get_entries(
+        &self,
+        page: usize,
+        per_page: usize,
+        tags: &[String],
+    ) -> Result<Vec<Value<FeedEntry>>> {
+        let res = if !tags.is_empty() {
+            let feeds = self.get_feeds_by_tags(tags)?;
+            let filter_by_feeds = |res: &Value<FeedEntry>| -> bool {
+                matches!(res.value(), Ok(entry) if feeds.contains(&entry.feed_id))
+            };
+
+            self.readable_entries()
+                .rev()
+                .filter(|res| res.as_ref().map_or(false, filter_by_feeds))
+                .skip(per_page * (page.max(1) - 1))
+                .take(per_page)
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            self.readable_entries()
+                .rev()
+                .skip(per_page * (page.max(1) - 1))
+                .take(per_page)
+                .collect::<Result<Vec<_>>>()?
+        };
+        Ok(res)
+    }
+
+    pub fn get_starred_entries(&self, page: usize, per_page: usize) -> Result<Vec<Value<FeedEntry>>> {
+        let res = self
+            .trees
+            .starred
+            .iter()
+            .keys()
+            .rev()
+            .map(|res| -> Result<Option<Value<FeedEntry>>> { self.get_entry(res?.key()?) })
+            .filter_map(Result::transpose)
+            .skip(per_page * (page.max(1) - 1))
+            .take(per_page)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(res)
+    }
+
+    pub fn get_subscriptions(&self) -> Result<Vec<Value<SubscriptionEntry>>> {
+        Ok(self
+            .trees
+            .subs
+            .iter()
+            .values()
+            .collect::<Result<_, SledBinError>>()?)
+    }
+
+    pub fn get_subscriptions_since(&self, since: OffsetDateTime) -> Result<Vec<Value<SubscriptionEntry>>> {
+        let filter_by_date = |res: &Value<SubscriptionEntry>| -> bool {
+            matches!(res.value(), Ok(sub) if sub.created_at > since)
+        };
+
+        let res = self
+            .trees
+            .subs
+            .iter()
+            .values()
+            .filter(|res| res.as_ref().map_or(false, filter_by_date))
+            .collect::<Result<_, SledBinError>>()?;
+        Ok(res)
+    }
+
+    pub fn get_subscription(&self, id: FeedId) -> Result<Option<Value<SubscriptionEntry>>> {
+        Ok(self.trees.subs.get(&id)?)
+    }
+
+    pub fn get_subscription_by_url(&self, feed_url: &str) -> Result<Option<Value<SubscriptionEntry>>> {
+        for res in self.trees.subs.iter().values() {
+            let res = res?;
+            if res.value()?.feed_url == feed_url {
+                return Ok(Some(res));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn add_subscription(&self, sub: &Subscription) -> Result<()> {
+        self.trees.subs.insert(&sub.feed_id, sub)?;
+        let key = SubscriberKey::new(sub.feed_id, &self.trees.name);
+        self.store.subscribers.insert(&key, &())?;
+        Ok(())
+    }
+
+    pub fn rename_subscription(&self, id: FeedId, title: &str) -> Result<Option<Value<SubscriptionEntry>>> {
+        let res = match self.trees.subs.get(&id)? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        let sub = Subscription {
+            title,
+            ..res.value()?
+        };
+        self.trees.subs.insert(&id, &sub)?;
+        self.get_subscription(id)
+    }
+
+    pub fn delete_subscription(&self, id: FeedId) -> Result<()> {
+        self.trees.subs.remove(&id)?;
+        self.store.subscribers.remove(&SubscriberKey::new(id, &self.trees.name))?;
+        self.trees.feed_options.remove(&id)?;
+
+        let mut removed = vec![];
+        for key in self.trees.modified.iter().keys() {
+            let entry_id = key?.key()?;
+            match self.store.get_entry(entry_id)? {
+                Some(res) if res.value()?.feed_id == id => removed.push(entry_id),
+                _ => {}
+            }
+        }
+        self.forget_modified(removed)
+    }
+
+    pub fn get_feed_options(&self, id: FeedId) -> Result<FeedOptions> {
+        match self.trees.feed_options.get(&id)? {
+            Some(res) => Ok(res.value()?),
+            None => Ok(FeedOptions::default()),
+        }
+    }
+
+    pub fn set_feed_options(&self, id: FeedId, options: &FeedOptions) -> Result<()> {
+        self.trees.feed_options.insert(&id, options)?;
+        Ok(())
+    }
+
+    // saves an entry of the pages feed, pages are only visible to the user that saved them
+    pub fn add_page(&self, entry: Entry, extras: EntryExtras) -> Result<()> {
+        let id = entry.id;
+        if self.store.insert_new_entry(entry, extras)? {
+            self.trees.unread.insert(&id, &())?;
+        }
+        self.trees.pages.insert(&id, &())?;
+        Ok(())
+    }
+
+    pub fn get_entry(&self, id: EntryId) -> Result<Option<Value<FeedEntry>>> {
+        match self.store.get_entry(id)? {
+            Some(res) if self.is_readable(&res.value()?)? => Ok(Some(res)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn get_entry_seq(&self, id: EntryId) -> Result<Option<u64>> {
+        match self.store.entry_seqs.get(&id)? {
+            Some(res) => Ok(Some(res.value()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_entry_by_seq(&self, seq: u64) -> Result<Option<Value<FeedEntry>>> {
+        self.entries_by_seq(seq..=seq)
+            .next()
+            .map(|res| res.map(|(_, entry)| entry))
+            .transpose()
+    }
+
+    // readable entries along with their sequence numbers in the order they were stored in,
+    // APIs that page by item IDs use the sequence numbers as IDs
+    pub fn entries_by_seq<R: RangeBounds<u64>>(
+        &self,
+        range: R,
+    ) -> impl DoubleEndedIterator<Item = Result<(u64, Value<FeedEntry>)>> + '_ {
+        let start = match range.start_bound() {
+            Bound::Included(seq) => Bound::Included(EntrySeqKey::new(*seq, EntryId(u64::MIN))),
+            Bound::Excluded(seq) => Bound::Excluded(EntrySeqKey::new(*seq, EntryId(u64::MAX))),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(seq) => Bound::Included(EntrySeqKey::new(*seq, EntryId(u64::MAX))),
+            Bound::Excluded(seq) => Bound::Excluded(EntrySeqKey::new(*seq, EntryId(u64::MIN))),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.store
+            .seq_index
+            .range((start, end))
+            .keys()
+            .map(|key| -> Result<Option<(u64, Value<FeedEntry>)>> {
+                let key = key?.key()?;
+                Ok(self.get_entry(key.entry_id)?.map(|entry| (key.seq(), entry)))
+            })
+            .filter_map(Result::transpose)
+    }
+
+    // number of readable entries, feeds are counted as a whole so that entries don't have to be read
+    pub fn count_entries(&self) -> Result<u64> {
+        let mut count = self.trees.pages.iter().keys().count() as u64;
+        for sub in self.trees.subs.iter().values() {
+            let feed_id = sub?.value()?.feed_id;
+            if feed_id != FeedId::PAGES {
+                count += self.store.get_entry_count(feed_id)?;
+            }
+        }
+        Ok(count)
+    }
+
+    // returns unread entries of the feeds that were published no later than the given date
+    pub fn get_unread_ids_by_feeds(&self, feeds: &[FeedId], before: OffsetDateTime) -> Result<Vec<EntryId>> {
+        let mut res = vec![];
+        for key in self.trees.unread.iter().keys() {
+            let entry = match self.get_entry(key?.key()?)? {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let entry = entry.value()?;
+            if feeds.contains(&entry.feed_id) && entry.published <= before {
+                res.push(entry.id);
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_entry_extras(&self, id: EntryId) -> Result<Option<Value<ExtrasEntry>>> {
+        Ok(self.store.extras.get(&id)?)
+    }
+
+    fn readable_entries(&self) -> impl DoubleEndedIterator<Item = Result<Value<FeedEntry>>> + '_ {
+        self.store
+            .entries
+            .iter()
+            .values()
+            .map(|res| -> Result<Option<Value<FeedEntry>>> {
+                let res = res?;
+                Ok(self.is_readable(&res.value()?)?.then_some(res))
+            })
+            .filter_map(Result::transpose)
+    }
+
+    fn is_readable(&self, entry: &Entry) -> Result<bool> {
+        if entry.feed_id == FeedId::PAGES {
+            Ok(self.trees.pages.get(&entry.id)?.is_some())
+        } else {
+            Ok(self.trees.subs.get(&entry.feed_id)?.is_some())
+        }
+    }
+
+    // returns entries that contain all of the terms in the text
+    pub fn search_text(&self, text: &str) -> Result<HashSet<EntryId>> {
+        let mut matched: Option<HashSet<EntryId>> = None;
+        for term in search::index_terms(text) {
+            let ids = self.store.get_term_entries(&term)?;
+            matched = Some(match matched {
+                Some(matched) => ids.into_iter().filter(|id| matched.contains(id)).collect(),
+                None => ids,
+            });
+            if matches!(&matched, Some(matched) if matched.is_empty()) {
+                break;
+            }
+        }
+        Ok(matched.unwrap_or_default())
+    }
+
+    pub fn search_entries_by_text(
+        &self,
+        text: &str,
+        filter: &SearchFilter,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<Value<FeedEntry>>> {
+        let matched = self.search_text(text)?;
+        if matched.is_empty() {
+            return Ok(vec![]);
+        }
+        let feeds = if filter.tags.is_empty() {
+            None
+        } else {
+            Some(self.get_feeds_by_tags(&filter.tags)?)
+        };
+
+        // visit the matches newest first
+        let mut matched = matched.into_iter().collect::<Vec<_>>();
+        matched.sort_unstable_by_key(|id| Reverse(id.date_key()));
+
+        let mut res = vec![];
+        let mut skipped = 0;
+        for id in matched {
+            let entry = match self.store.entries.get(&id)? {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let feed_id = entry.value()?.feed_id;
+            if !self.is_readable(&entry.value()?)?
+                || matches!(filter.feed_id, Some(id) if id != feed_id)
+                || matches!(&feeds, Some(feeds) if !feeds.contains(&feed_id))
+                || matches!(filter.read, Some(read) if self.trees.unread.get(&id)?.is_some() == read)
+                || matches!(filter.starred, Some(starred) if self.trees.starred.get(&id)?.is_some() != starred)
+            {
+                continue;
+            }
+            if skipped < per_page * (page.max(1) - 1) {
+                skipped += 1;
+                continue;
+            }
+            res.push(entry);
+            if res.len() == per_page {
+                break;
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_taggings(&self) -> Result<Vec<Value<TaggingEntry>>> {
+        let res = self
+            .trees
+            .taggings
+            .iter()
+            .values()
+            .collect::<Result<_, SledBinError>>()?;
+        Ok(res)
+    }
+
+    pub fn new_tagging_id(&self) -> Result<TaggingId> {
+        Ok(TaggingId(self.store.db.generate_id()?))
+    }
+
+    pub fn get_tagging(&self, id: TaggingId) -> Result<Option<Value<TaggingEntry>>> {
+        Ok(self.trees.taggings.get(&id)?)
+    }
+
+    // returns the ID of an existing tagging with the same feed and name instead of inserting a duplicate
+    pub fn add_tagging(&self, tagging: &Tagging) -> Result<Option<TaggingId>> {
+        let key = TaggingKey::new(tagging.feed_id, tagging.name);
+        let res = (&self.trees.taggings, &self.trees.tagging_index).transaction(|taggings, index| {
+            if let Some(existing) = index.get(&key)? {
+                return Ok(Some(existing.value()?));
+            }
+            taggings.insert(&tagging.id, tagging)?;
+            index.insert(&key, &tagging.id)?;
+            Ok(None)
+        })?;
+        Ok(res)
+    }
+
+    pub fn delete_tagging(&self, id: TaggingId) -> Result<()> {
+        (&self.trees.taggings, &self.trees.tagging_index).transaction(|taggings, index| {
+            if let Some(res) = taggings.remove(&id)? {
+                let tagging = res.value()?;
+                index.remove(&TaggingKey::new(tagging.feed_id, tagging.name))?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_tags(&self) -> Result<Vec<Tag>> {
+        let mut feeds_by_tag: BTreeMap<String, Vec<FeedId>> = BTreeMap::new();
+        for res in self.trees.taggings.iter().values() {
+            let res = res?;
+            let tagging = res.value()?;
+            feeds_by_tag
+                .entry(tagging.name.to_owned())
+                .or_default()
+                .push(tagging.feed_id);
+        }
+
+        let mut unread_by_feed: HashMap<FeedId, usize> = HashMap::new();
+        for res in self.trees.unread.iter().keys() {
+            if let Some(entry) = self.store.entries.get(&res?.key()?)? {
+                *unread_by_feed.entry(entry.value()?.feed_id).or_default() += 1;
+            }
+        }
+
+        let res = feeds_by_tag
+            .into_iter()
+            .map(|(name, feeds)| Tag {
+                unread_count: feeds.iter().filter_map(|id| unread_by_feed.get(id)).sum(),
+                feed_count: feeds.len(),
+                name,
+            })
+            .collect();
+        Ok(res)
+    }
+
+    // folders are tags that can exist without any feeds, they're used by APIs that create them up front
+    pub fn get_folders(&self) -> Result<BTreeSet<String>> {
+        let mut res = BTreeSet::new();
+        for key in self.trees.folders.iter().keys() {
+            res.insert(key?.key()?.to_owned());
+        }
+        for tag in self.get_tags()? {
+            res.insert(tag.name);
+        }
+        Ok(res)
+    }
+
+    pub fn add_folder(&self, name: &str) -> Result<()> {
+        self.trees.folders.insert(&name, &())?;
+        Ok(())
+    }
+
+    pub fn rename_tag(&self, old_name: &str, new_name: &str) -> Result<()> {
+        if self.trees.folders.remove(&old_name)?.is_some() {
+            self.trees.folders.insert(&new_name, &())?;
+        }
+        let renamed = self.get_taggings_by_name(old_name)?;
+        (&self.trees.taggings, &self.trees.tagging_index).transaction(|taggings, index| {
+            for &(id, feed_id) in &renamed {
+                index.remove(&TaggingKey::new(feed_id, old_name))?;
+                // the feed might already be tagged with the new name
+                if index.get(&TaggingKey::new(feed_id, new_name))?.is_some() {
+                    taggings.remove(&id)?;
+                } else {
+                    taggings.insert(&id, &Tagging::new(id, feed_id, new_name))?;
+                    index.insert(&TaggingKey::new(feed_id, new_name), &id)?;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn delete_tag(&self, name: &str) -> Result<()> {
+        self.trees.folders.remove(&name)?;
+        let deleted = self.get_taggings_by_name(name)?;
+        (&self.trees.taggings, &self.trees.tagging_index).transaction(|taggings, index| {
+            for &(id, feed_id) in &deleted {
+                taggings.remove(&id)?;
+                index.remove(&TaggingKey::new(feed_id, name))?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn get_taggings_by_name(&self, name: &str) -> Result<Vec<(TaggingId, FeedId)>> {
+        let mut res = vec![];
+        for tagging in self.trees.taggings.iter().values() {
+            let tagging = tagging?;
+            let tagging = tagging.value()?;
+            if tagging.name == name {
+                res.push((tagging.id, tagging.feed_id));
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_saved_searches(&self) -> Result<Vec<Value<SavedSearchEntry>>> {
+        let res = self
+            .trees
+            .saved_searches
+            .iter()
+            .values()
+            .collect::<Result<_, SledBinError>>()?;
+        Ok(res)
+    }
+
+    pub fn get_saved_search(&self, id: SavedSearchId) -> Result<Option<Value<SavedSearchEntry>>> {
+        Ok(self.trees.saved_searches.get(&id)?)
+    }
+
+    pub fn new_saved_search_id(&self) -> Result<SavedSearchId> {
+        Ok(SavedSearchId(self.store.db.generate_id()?))
+    }
+
+    pub fn add_saved_search(&self, search: &SavedSearch) -> Result<()> {
+        self.trees.saved_searches.insert(&search.id, search)?;
+        Ok(())
+    }
+
+    pub fn update_saved_search(
+        &self,
+        id: SavedSearchId,
+        name: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Option<Value<SavedSearchEntry>>> {
+        let res = match self.trees.saved_searches.get(&id)? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        let search = res.value()?;
+        let search = SavedSearch::new(id, name.unwrap_or(search.name), query.unwrap_or(search.query));
+        self.trees.saved_searches.insert(&id, &search)?;
+        self.get_saved_search(id)
+    }
+
+    pub fn delete_saved_search(&self, id: SavedSearchId) -> Result<()> {
+        self.trees.saved_searches.remove(&id)?;
+        Ok(())
+    }
+
+    pub fn search_entries(
+        &self,
+        query: &SearchQuery,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<Value<FeedEntry>>> {
+        let feeds = self.get_searchable_feeds()?;
+        let res = self
+            .readable_entries()
+            .rev()
+            .filter(|res| res.as_ref().map_or(true, |res| matches_query(res, query, &feeds)))
+            .skip(per_page * (page.max(1) - 1))
+            .take(per_page)
+            .collect::<Result<_>>()?;
+        Ok(res)
+    }
+
+    pub fn search_entry_ids(&self, query: &SearchQuery) -> Result<Vec<EntryId>> {
+        let feeds = self.get_searchable_feeds()?;
+        let mut res = vec![];
+        for entry in self.readable_entries().rev() {
+            let entry = entry?;
+            if matches_query(&entry, query, &feeds) {
+                res.push(entry.value()?.id);
+            }
+        }
+        Ok(res)
+    }
+
+    // lower case feed titles and tags, used to match entries against search queries
+    fn get_searchable_feeds(&self) -> Result<HashMap<FeedId, (String, Vec<String>)>> {
+        let mut feeds: HashMap<FeedId, (String, Vec<String>)> = HashMap::new();
+        for res in self.trees.subs.iter().values() {
+            let res = res?;
+            let sub = res.value()?;
+            feeds.entry(sub.feed_id).or_default().0 = sub.title.to_lowercase();
+        }
+        for res in self.trees.taggings.iter().values() {
+            let res = res?;
+            let tagging = res.value()?;
+            feeds
+                .entry(tagging.feed_id)
+                .or_default()
+                .1
+                .push(tagging.name.to_lowercase());
+        }
+        Ok(feeds)
+    }
+
+    pub fn get_shared_feeds(&self) -> Result<Vec<(String, FeedSource)>> {
+        let mut res = vec![];
+        for key in self.trees.shared_feeds.iter().keys() {
+            let token = key?.key()?.to_owned();
+            if let Some(source) = self.get_shared_feed(&token)? {
+                res.push((token, source));
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_shared_feed(&self, token: &str) -> Result<Option<FeedSource>> {
+        match self.trees.shared_feeds.get(&token)? {
+            Some(res) => Ok(Some(res.value()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn add_shared_feed(&self, token: &str, source: &FeedSource) -> Result<()> {
+        self.trees.shared_feeds.insert(&token, source)?;
+        Ok(())
+    }
+
+    pub fn delete_shared_feed(&self, token: &str) -> Result<bool> {
+        Ok(self.trees.shared_feeds.remove(&token)?.is_some())
+    }
+
+    pub fn get_article(&self, id: EntryId) -> Result<Option<Value<ArticleEntry>>> {
+        self.store.get_article(id)
+    }
+
+    pub fn add_article(&self, id: EntryId, article: &ExtractedArticle) -> Result<()> {
+        self.store.add_article(id, article)
+    }
+
+    // hosts of the sites the user is subscribed to that have an icon
+    pub fn get_icon_hosts(&self) -> Result<Vec<String>> {
+        let mut res = vec![];
+        for host in self.get_site_hosts()? {
+            if self.store.get_icon(&host)?.is_some() {
+                res.push(host);
+            }
+        }
+        Ok(res)
+    }
+
+    // icons are only served for the sites the user is subscribed to
+    pub fn get_icon(&self, host: &str) -> Result<Option<Value<IconEntry>>> {
+        if !self.get_site_hosts()?.contains(host) {
+            return Ok(None);
+        }
+        self.store.get_icon(host)
+    }
+
+    fn get_site_hosts(&self) -> Result<BTreeSet<String>> {
+        let mut res = BTreeSet::new();
+        for sub in self.trees.subs.iter().values() {
+            let sub = sub?;
+            if let Ok(url) = sub.value()?.site_url.parse::<Uri>() {
+                res.extend(url.host().map(str::to_owned));
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_feeds_by_tags(&self, tags: &[String]) -> Result<Vec<FeedId>> {
+        let mut feeds = vec![];
+        for tagging in self.trees.taggings.iter().values() {
+            let tagging = tagging?;
+            let tagging = tagging.value()?;
+            if tags.iter().any(|str| str == tagging.name) {
+                feeds.push(tagging.feed_id);
+            }
+        }
+        Ok(feeds)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SubscriptionEntry;
+
+impl<'a> TreeEntry<'a> for SubscriptionEntry {
+    type Key = FeedId;
+    type Val = Subscription<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct MarkedEntry;
+
+impl<'a> TreeEntry<'a> for MarkedEntry {
+    type Key = EntryId;
+    type Val = ();
+}
+
+#[derive(Debug, Default)]
+pub struct FeedEntry;
+
+impl<'a> TreeEntry<'a> for FeedEntry {
+    type Key = EntryId;
+    type Val = Entry<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct TaggingEntry;
+
+impl<'a> TreeEntry<'a> for TaggingEntry {
+    type Key = TaggingId;
+    type Val = Tagging<'a>;
+}
+
+fn clear_tree<E: for<'a> TreeEntry<'a>>(tree: &Tree<E>) -> Result<()> {
+    let mut batch = Batch::default();
+    for key in tree.iter().keys() {
+        batch.remove(&key?.key()?)?;
+    }
+    tree.apply_batch(batch)?;
+    Ok(())
+}
+
+fn matches_query(
+    res: &Value<FeedEntry>,
+    query: &SearchQuery,
+    feeds: &HashMap<FeedId, (String, Vec<String>)>,
+) -> bool {
+    let entry = match res.value() {
+        Ok(entry) => entry,
+        Err(_) => return false,
+    };
+    let (feed, tags) = feeds
+        .get(&entry.feed_id)
+        .map_or(("", &[][..]), |(feed, tags)| (feed.as_str(), tags.as_slice()));
+    let doc = Document::new(
+        entry.title,
+        &[entry.content, entry.summary],
+        entry.author,
+        feed,
+        tags,
+    );
+    query.matches(&doc)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaggingKey<'a> {
+    feed_id: FeedId,
+    name: &'a str,
+}
+
+impl<'a> TaggingKey<'a> {
+    fn new(feed_id: FeedId, name: &'a str) -> Self {
+        Self { feed_id, name }
+    }
+}
+
+// entries are indexed under each of their terms, so that all entries of a term share a key prefix
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchTermKey<'a> {
+    term: &'a str,
+    entry_id: EntryId,
+}
+
+impl<'a> SearchTermKey<'a> {
+    fn new(term: &'a str, entry_id: EntryId) -> Self {
+        Self { term, entry_id }
+    }
+}
+
+// sequence numbers are stored big-endian, so that the keys are ordered by number
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntrySeqKey {
+    seq: [u8; 8],
+    entry_id: EntryId,
+}
+
+impl EntrySeqKey {
+    fn new(seq: u64, entry_id: EntryId) -> Self {
+        Self {
+            seq: seq.to_be_bytes(),
+            entry_id,
+        }
+    }
+
+    fn seq(&self) -> u64 {
+        u64::from_be_bytes(self.seq)
+    }
+}
+
+// timestamps are stored big-endian, so that the keys are ordered by time, times before
+// the epoch are not expected and stored as the epoch
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifiedKey {
+    modified: [u8; 8],
+    entry_id: EntryId,
+}
+
+impl ModifiedKey {
+    fn new(modified: i64, entry_id: EntryId) -> Self {
+        Self {
+            modified: (modified.max(0) as u64).to_be_bytes(),
+            entry_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriberKey<'a> {
+    feed_id: FeedId,
+    user: &'a str,
+}
+
+impl<'a> SubscriberKey<'a> {
+    fn new(feed_id: FeedId, user: &'a str) -> Self {
+        Self { feed_id, user }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TaggingIndexEntry;
+
+impl<'a> TreeEntry<'a> for TaggingIndexEntry {
+    type Key = TaggingKey<'a>;
+    type Val = TaggingId;
+}
+
+#[derive(Debug, Default)]
+pub struct SavedSearchEntry;
+
+impl<'a> TreeEntry<'a> for SavedSearchEntry {
+    type Key = SavedSearchId;
+    type Val = SavedSearch<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct SearchIndexEntry;
+
+impl<'a> TreeEntry<'a> for SearchIndexEntry {
+    type Key = SearchTermKey<'a>;
+    type Val = ();
+}
+
+#[derive(Debug, Default)]
+pub struct EntrySeqEntry;
+
+impl<'a> TreeEntry<'a> for EntrySeqEntry {
+    type Key = EntryId;
+    type Val = u64;
+}
+
+#[derive(Debug, Default)]
+pub struct EntryCountEntry;
+
+impl<'a> TreeEntry<'a> for EntryCountEntry {
+    type Key = FeedId;
+    type Val = u64;
+}
+
+#[derive(Debug, Default)]
+pub struct SeqIndexEntry;
+
+impl<'a> TreeEntry<'a> for SeqIndexEntry {
+    type Key = EntrySeqKey;
+    type Val = ();
+}
+
+#[derive(Debug, Default)]
+pub struct SubscriberEntry;
+
+impl<'a> TreeEntry<'a> for SubscriberEntry {
+    type Key = SubscriberKey<'a>;
+    type Val = ();
+}
+
+#[derive(Debug, Default)]
+pub struct RecentlyReadEntry;
+
+impl<'a> TreeEntry<'a> for RecentlyReadEntry {
+    type Key = EntryId;
+    type Val = i64;
+}
+
+#[derive(Debug, Default)]
+pub struct ExtrasEntry;
+
+impl<'a> TreeEntry<'a> for ExtrasEntry {
+    type Key = EntryId;
+    type Val = EntryExtras<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct IconEntry;
+
+impl<'a> TreeEntry<'a> for IconEntry {
+    type Key = &'a str;
+    type Val = Icon<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct ArticleEntry;
+
+impl<'a> TreeEntry<'a> for ArticleEntry {
+    type Key = EntryId;
+    type Val = ExtractedArticle<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct FeedOptionsEntry;
+
+impl<'a> TreeEntry<'a> for FeedOptionsEntry {
+    type Key = FeedId;
+    type Val = FeedOptions;
+}
+
+#[derive(Debug, Default)]
+pub struct ModifiedEntry;
+
+impl<'a> TreeEntry<'a> for ModifiedEntry {
+    type Key = EntryId;
+    type Val = i64;
+}
+
+#[derive(Debug, Default)]
+pub struct ModifiedIndexEntry;
+
+impl<'a> TreeEntry<'a> for ModifiedIndexEntry {
+    type Key = ModifiedKey;
+    type Val = ();
+}
+
+#[derive(Debug, Default)]
+pub struct FolderEntry;
+
+impl<'a> TreeEntry<'a> for FolderEntry {
+    type Key = &'a str;
+    type Val = ();
+}
+
+#[derive(Debug, Default)]
+pub struct SharedFeedEntry;
+
+impl<'a> TreeEntry<'a> for SharedFeedEntry {
+    type Key = &'a str;
+    type Val = FeedSource;
+}
+
+#[derive(Debug, Default)]
+pub struct ApiTokenEntry;
+
+impl<'a> TreeEntry<'a> for ApiTokenEntry {
+    type Key = &'a str;
+    type Val = ApiToken<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct AdminPasswordEntry;
+
+impl<'a> TreeEntry<'a> for AdminPasswordEntry {
+    type Key = &'a str;
+    // an Argon2 hash in the PHC string format
+    type Val = &'a str;
+}
+
+#[derive(Debug, Default)]
+pub struct MetaEntry;
+
+impl<'a> TreeEntry<'a> for MetaEntry {
+    type Key = &'a str;
+    type Val = u32;
+}
+
+#[derive(Debug, Default)]
+pub struct UserEntry;
+
+impl<'a> TreeEntry<'a> for UserEntry {
+    type Key = &'a str;
+    // the prefix of the names of the trees that hold the data of the user
+    type Val = &'a str;
+}
+
+#[derive(Debug, Default)]
+pub struct AccountEntry;
+
+impl<'a> TreeEntry<'a> for AccountEntry {
+    type Key = &'a str;
+    type Val = Account<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_store() -> (tempfile::TempDir, Arc<Store>) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(Store::new(dir.path()).unwrap());
+        (dir, store)
+    }
+
+    fn add_user(store: &Arc<Store>, name: &str) -> Arc<Repo> {
+        store
+            .set_account(&Account {
+                name,
+                password_hash: "",
+                fever_key: "",
+                reader_token: "",
+                disabled: false,
+                created_at: OffsetDateTime::now_utc(),
+            })
+            .unwrap();
+        store.get_repo(name).unwrap().unwrap()
+    }
+
+    fn subscribe(repo: &Repo, feed_id: FeedId) {
+        repo.add_subscription(&Subscription {
+            id: feed_id,
+            feed_id,
+            title: "Feed",
+            feed_url: "https://example.com/feed",
+            site_url: "https://example.com",
+            created_at: OffsetDateTime::now_utc(),
+        })
+        .unwrap();
+    }
+
+    fn add_feed_entry(store: &Arc<Store>, feed_id: FeedId) -> EntryId {
+        let entry = Entry {
+            feed_id,
+            ..Entry::from_page("https://example.com/post", Some("Post"), None, OffsetDateTime::now_utc())
+        };
+        let id = entry.id;
+        let extras = EntryExtras::from_entry(&entry);
+        store.insert_entry(entry, extras).unwrap();
+        id
+    }
+
+    #[test]
+    fn pages_of_other_users_cannot_be_starred_or_read() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let bob = add_user(&store, "bob");
+
+        let entry = Entry::from_page("https://example.com/private", Some("Private"), None, OffsetDateTime::now_utc());
+        let id = entry.id;
+        let extras = EntryExtras::from_entry(&entry);
+        alice.add_page(entry, extras).unwrap();
+
+        assert!(bob.add_starred([id]).unwrap().is_empty());
+        assert!(bob.add_unread([id]).unwrap().is_empty());
+        assert!(bob.add_recently_read([id]).unwrap().is_empty());
+        assert!(bob.get_entry(id).unwrap().is_none());
+        assert!(bob.get_starred().unwrap().is_empty());
+        assert!(bob.get_unread().unwrap().is_empty());
+        assert!(bob.get_starred_entries(1, 10).unwrap().is_empty());
+        assert!(bob.get_modified(id).unwrap().is_none());
+        assert!(alice.get_entry(id).unwrap().is_some());
+    }
+
+    #[test]
+    fn entries_of_feeds_are_only_readable_by_subscribers() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let bob = add_user(&store, "bob");
+        let feed_id = store.new_feed_id().unwrap();
+        subscribe(&alice, feed_id);
+        let id = add_feed_entry(&store, feed_id);
+
+        assert_eq!(alice.get_unread().unwrap().len(), 1);
+        assert!(bob.get_unread().unwrap().is_empty());
+        assert!(bob.add_starred([id]).unwrap().is_empty());
+        assert!(bob.get_entry(id).unwrap().is_none());
+        assert!(bob.get_entries(1, 10, &[]).unwrap().is_empty());
+        assert_eq!(alice.get_entries(1, 10, &[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn starred_entries_do_not_grant_access() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let feed_id = store.new_feed_id().unwrap();
+        subscribe(&alice, feed_id);
+        let id = add_feed_entry(&store, feed_id);
+
+        assert_eq!(alice.add_starred([id]).unwrap(), vec![id]);
+        alice.delete_subscription(feed_id).unwrap();
+        assert!(alice.get_entry(id).unwrap().is_none());
+        assert!(alice.get_starred_entries(1, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pages_are_moved_to_the_new_feed_id() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        alice
+            .add_subscription(&Subscription {
+                id: LEGACY_PAGES_FEED_ID,
+                feed_id: LEGACY_PAGES_FEED_ID,
+                ..Subscription::pages(OffsetDateTime::now_utc())
+            })
+            .unwrap();
+        let entry = Entry {
+            feed_id: LEGACY_PAGES_FEED_ID,
+            ..Entry::from_page("https://example.com/page", Some("Page"), None, OffsetDateTime::now_utc())
+        };
+        let id = entry.id;
+        let extras = EntryExtras::from_entry(&entry);
+        store.insert_new_entry(entry, extras).unwrap();
+        alice.trees.pages.insert(&id, &()).unwrap();
+        let tagging = Tagging::new(alice.new_tagging_id().unwrap(), LEGACY_PAGES_FEED_ID, "saved");
+        alice.add_tagging(&tagging).unwrap();
+
+        store.move_pages_feed().unwrap();
+
+        assert!(alice.get_subscription(LEGACY_PAGES_FEED_ID).unwrap().is_none());
+        let sub = alice.get_subscription(FeedId::PAGES).unwrap().unwrap();
+        assert_eq!(sub.value().unwrap().feed_id, FeedId::PAGES);
+        let entry = alice.get_entry(id).unwrap().unwrap();
+        assert_eq!(entry.value().unwrap().feed_id, FeedId::PAGES);
+        assert_eq!(store.get_subscribers(FeedId::PAGES).unwrap(), vec!["alice".to_owned()]);
+        assert!(store.get_subscribers(LEGACY_PAGES_FEED_ID).unwrap().is_empty());
+        assert_eq!(alice.get_feeds_by_tags(&["saved".to_owned()]).unwrap(), vec![FeedId::PAGES]);
+    }
+
+    #[test]
+    fn users_have_distinct_ids_that_survive_reopening() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let bob = add_user(&store, "bob");
+        assert_eq!(alice.user_id(), 1);
+        assert_ne!(alice.user_id(), bob.user_id());
+
+        store.user_trees.lock().unwrap().clear();
+        assert_eq!(store.get_repo("bob").unwrap().unwrap().user_id(), bob.user_id());
+    }
+
+    #[test]
+    fn entries_are_counted_per_user() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let bob = add_user(&store, "bob");
+        let feed_id = store.new_feed_id().unwrap();
+        subscribe(&alice, feed_id);
+        add_feed_entry(&store, feed_id);
+        let page = Entry::from_page("https://example.com/page", Some("Page"), None, OffsetDateTime::now_utc());
+        let extras = EntryExtras::from_entry(&page);
+        bob.add_page(page, extras).unwrap();
+
+        assert_eq!(alice.count_entries().unwrap(), 1);
+        assert_eq!(bob.count_entries().unwrap(), 1);
+
+        store.entry_counts.remove(&feed_id).unwrap();
+        store.count_stored_entries().unwrap();
+        assert_eq!(alice.count_entries().unwrap(), 1);
+    }
+
+    #[test]
+    fn modified_entries_are_found_by_time_and_dropped_with_their_feed() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let feed_id = store.new_feed_id().unwrap();
+        subscribe(&alice, feed_id);
+        let id = add_feed_entry(&store, feed_id);
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        alice.add_starred([id]).unwrap();
+        alice.delete_starred([id]).unwrap();
+        assert_eq!(alice.get_modified_since(now - 60).unwrap(), vec![id]);
+        assert!(alice.get_modified_since(now + 60).unwrap().is_empty());
+
+        alice.delete_subscription(feed_id).unwrap();
+        assert!(alice.get_modified(id).unwrap().is_none());
+        assert!(alice.get_modified_since(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_results_are_sorted_newest_first() {
+        let (_dir, store) = open_store();
+        let alice = add_user(&store, "alice");
+        let now = OffsetDateTime::now_utc();
+        let mut ids = vec![];
+        let pages = [
+            ("https://example.com/a", 30),
+            ("https://example.com/b", 1),
+            ("https://example.com/c", 7),
+        ];
+        for (url, days) in pages {
+            let entry = Entry::from_page(url, Some("Rust release"), None, now - time::Duration::days(days));
+            ids.push((days, entry.id));
+            let extras = EntryExtras::from_entry(&entry);
+            alice.add_page(entry, extras).unwrap();
+        }
+        ids.sort_unstable();
+
+        let res = alice
+            .search_entries_by_text("rust", &SearchFilter::default(), 1, 10)
+            .unwrap()
+            .iter()
+            .map(|res| res.value().unwrap().id)
+            .collect::<Vec<_>>();
+        assert_eq!(res, ids.into_iter().map(|(_, id)| id).collect::<Vec<_>>());
+    }
 }
@@ -1,21 +1,45 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use rsst::client::RssError;
 use serde_json::json;
 use thiserror::Error;
 
+use crate::search::QueryError;
+
 #[derive(Debug, Error)]
 pub enum ServiceEror {
     #[error("db error: {0}")]
     DbError(#[from] sled_bincode::Error),
     #[error("transaction failed: {0}")]
     TransactionFailed(#[from] sled_bincode::TransactionError),
-    #[error(
-        "{0} ({})",
-        if matches!(.0, RssError::XmlDecode(_)) { "possibly RSS 1.0" } else { "RSS lookup failed" })
-    ]
-    RssError(#[from] RssError),
+    #[error("invalid feed: {0}")]
+    InvalidFeed(String),
+    #[error("http error: {0}")]
+    HttpError(#[from] hyper::Error),
+    #[error("invalid request: {0}")]
+    InvalidRequest(#[from] hyper::http::Error),
+    #[error("invalid url: {0}")]
+    InvalidUri(#[from] hyper::http::uri::InvalidUri),
+    #[error("unexpected http status: {0}")]
+    HttpStatus(StatusCode),
+    #[error("too many redirects")]
+    TooManyRedirects,
+    #[error("only http and https urls are supported")]
+    UnsupportedScheme,
+    #[error("request timed out")]
+    Timeout,
+    #[error("response exceeds {0} bytes")]
+    ResponseTooLarge(usize),
+    #[error(transparent)]
+    InvalidQuery(#[from] QueryError),
+    #[error("could not generate random bytes")]
+    RandomFailed,
+    #[error("could not hash the password: {0}")]
+    PasswordHash(argon2::password_hash::Error),
+    #[error("the password has to differ from the password of the account")]
+    ReusedPassword,
+    #[error("background task failed: {0}")]
+    TaskFailed(#[from] tokio::task::JoinError),
 }
 
 pub type Result<A, E = ServiceEror> = std::result::Result<A, E>;
@@ -26,9 +50,25 @@ impl From<sled_bincode::SledError> for ServiceEror {
     }
 }
 
+impl From<ring::error::Unspecified> for ServiceEror {
+    fn from(_: ring::error::Unspecified) -> Self {
+        Self::RandomFailed
+    }
+}
+
+impl From<argon2::password_hash::Error> for ServiceEror {
+    fn from(err: argon2::password_hash::Error) -> Self {
+        Self::PasswordHash(err)
+    }
+}
+
 impl IntoResponse for ServiceEror {
     fn into_response(self) -> Response {
+        let status = match self {
+            Self::InvalidQuery(_) | Self::ReusedPassword => StatusCode::UNPROCESSABLE_ENTITY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
         let body = json!({"message": self.to_string()});
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+        (status, Json(body)).into_response()
     }
 }
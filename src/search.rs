@@ -0,0 +1,393 @@
+use std::collections::BTreeSet;
+
+use thiserror::Error;
+
+use crate::html;
+use crate::types::{Entry, FeedId};
+
+const MIN_TERM_LENGTH: usize = 2;
+const MAX_TERM_LENGTH: usize = 32;
+// queries come from clients, the parser and the evaluation recurse over the query
+const MAX_QUERY_TOKENS: usize = 256;
+const MAX_QUERY_DEPTH: usize = 32;
+
+// a small query language used by saved searches, terms can be combined with
+// `AND`, `OR` and `NOT` (or a `-` prefix), grouped with parentheses and
+// limited to a single field with a prefix, e.g. `rust AND (title:release OR tag:"release notes")`
+#[derive(Debug, PartialEq)]
+pub enum SearchQuery {
+    Term(Field, String),
+    And(Box<SearchQuery>, Box<SearchQuery>),
+    Or(Box<SearchQuery>, Box<SearchQuery>),
+    Not(Box<SearchQuery>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Any,
+    Title,
+    Content,
+    Author,
+    Feed,
+    Tag,
+}
+
+impl Field {
+    fn from_prefix(str: &str) -> Option<Self> {
+        match str {
+            "title" => Some(Self::Title),
+            "content" => Some(Self::Content),
+            "author" => Some(Self::Author),
+            "feed" => Some(Self::Feed),
+            "tag" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid search query: {0}")]
+pub struct QueryError(&'static str);
+
+impl SearchQuery {
+    pub fn parse(str: &str) -> Result<Self, QueryError> {
+        let tokens = lex(str)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let query = parser.parse_or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(Token::RParen) => Err(QueryError("unbalanced parentheses")),
+            Some(_) => Err(QueryError("unexpected token")),
+        }
+    }
+
+    pub fn matches(&self, doc: &Document<'_>) -> bool {
+        match self {
+            Self::Term(field, term) => doc.matches(*field, term),
+            Self::And(lhs, rhs) => lhs.matches(doc) && rhs.matches(doc),
+            Self::Or(lhs, rhs) => lhs.matches(doc) || rhs.matches(doc),
+            Self::Not(query) => !query.matches(doc),
+        }
+    }
+}
+
+// searchable fields of an entry, all in lower case
+pub struct Document<'a> {
+    title: String,
+    content: String,
+    author: String,
+    feed: &'a str,
+    tags: &'a [String],
+}
+
+impl<'a> Document<'a> {
+    pub fn new(
+        title: Option<&str>,
+        content: &[Option<&str>],
+        author: Option<&str>,
+        feed: &'a str,
+        tags: &'a [String],
+    ) -> Self {
+        let content = content.iter().flatten().copied().collect::<Vec<_>>().join("\n");
+        Self {
+            title: title.unwrap_or_default().to_lowercase(),
+            content: content.to_lowercase(),
+            author: author.unwrap_or_default().to_lowercase(),
+            feed,
+            tags,
+        }
+    }
+
+    fn matches(&self, field: Field, term: &str) -> bool {
+        match field {
+            Field::Any => self.title.contains(term) || self.content.contains(term),
+            Field::Title => self.title.contains(term),
+            Field::Content => self.content.contains(term),
+            Field::Author => self.author.contains(term),
+            Field::Feed => self.feed.contains(term),
+            Field::Tag => self.tags.iter().any(|tag| tag == term),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(Field, String),
+}
+
+fn lex(str: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = vec![];
+    let mut rest = str.trim_start();
+    while let Some(char) = rest.chars().next() {
+        match char {
+            '(' => {
+                tokens.push(Token::LParen);
+                rest = &rest[1..];
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                rest = &rest[1..];
+            }
+            '-' => {
+                tokens.push(Token::Not);
+                rest = &rest[1..];
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                    .unwrap_or(rest.len());
+                let (word, remaining) = rest.split_at(end);
+                let (field, value) = match word.split_once(':') {
+                    Some((prefix, value)) => match Field::from_prefix(&prefix.to_lowercase()) {
+                        Some(field) => (field, value),
+                        None => (Field::Any, word),
+                    },
+                    None => (Field::Any, word),
+                };
+
+                if let Some(phrase) = value.strip_prefix('"') {
+                    // phrases extend until the closing quote, spaces and parentheses included
+                    let offset = word.len() - phrase.len();
+                    let phrase = &rest[offset..];
+                    let end = phrase.find('"').ok_or(QueryError("unterminated phrase"))?;
+                    tokens.push(Token::Term(field, phrase[..end].to_lowercase()));
+                    rest = &phrase[end + 1..];
+                } else {
+                    rest = remaining;
+                    match word {
+                        "AND" => tokens.push(Token::And),
+                        "OR" => tokens.push(Token::Or),
+                        "NOT" => tokens.push(Token::Not),
+                        _ if value.is_empty() => return Err(QueryError("empty search term")),
+                        _ => tokens.push(Token::Term(field, value.to_lowercase())),
+                    }
+                }
+            }
+        }
+        if tokens.len() > MAX_QUERY_TOKENS {
+            return Err(QueryError("query is too long"));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    // the number of enclosing groups and negations
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_or(&mut self) -> Result<SearchQuery, QueryError> {
+        let mut query = self.parse_and()?;
+        while self.tokens.get(self.pos) == Some(&Token::Or) {
+            self.pos += 1;
+            query = SearchQuery::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<SearchQuery, QueryError> {
+        let mut query = self.parse_unary()?;
+        loop {
+            match self.tokens.get(self.pos) {
+                Some(Token::And) => self.pos += 1,
+                // adjacent terms are implicitly combined with `AND`
+                Some(Token::LParen | Token::Not | Token::Term(_, _)) => {}
+                _ => return Ok(query),
+            }
+            query = SearchQuery::And(Box::new(query), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<SearchQuery, QueryError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .ok_or(QueryError("unexpected end of query"))?;
+        self.pos += 1;
+        match token {
+            Token::Not => self.nested(|parser| Ok(SearchQuery::Not(Box::new(parser.parse_unary()?)))),
+            Token::LParen => self.nested(|parser| {
+                let query = parser.parse_or()?;
+                match parser.tokens.get(parser.pos) {
+                    Some(Token::RParen) => {
+                        parser.pos += 1;
+                        Ok(query)
+                    }
+                    _ => Err(QueryError("unbalanced parentheses")),
+                }
+            }),
+            Token::Term(field, term) => Ok(SearchQuery::Term(*field, term.clone())),
+            Token::RParen | Token::And | Token::Or => Err(QueryError("unexpected token")),
+        }
+    }
+
+    fn nested<F>(&mut self, parse: F) -> Result<SearchQuery, QueryError>
+    where
+        F: FnOnce(&mut Self) -> Result<SearchQuery, QueryError>,
+    {
+        if self.depth >= MAX_QUERY_DEPTH {
+            return Err(QueryError("query is nested too deeply"));
+        }
+        self.depth += 1;
+        let res = parse(self);
+        self.depth -= 1;
+        res
+    }
+}
+
+// filters applied to full-text search results
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+    pub feed_id: Option<FeedId>,
+    pub tags: Vec<String>,
+    pub read: Option<bool>,
+    pub starred: Option<bool>,
+}
+
+// splits text into the normalized terms stored in the full-text index
+pub fn index_terms(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|str| (MIN_TERM_LENGTH..=MAX_TERM_LENGTH).contains(&str.chars().count()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+pub fn entry_terms(entry: &Entry<'_>) -> BTreeSet<String> {
+    let mut terms = BTreeSet::new();
+    for text in [entry.title, entry.author].into_iter().flatten() {
+        terms.extend(index_terms(text));
+    }
+    for html in [entry.summary, entry.content].into_iter().flatten() {
+        terms.extend(index_terms(&html::strip_tags(html)));
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(field: Field, str: &str) -> SearchQuery {
+        SearchQuery::Term(field, str.to_owned())
+    }
+
+    fn and(lhs: SearchQuery, rhs: SearchQuery) -> SearchQuery {
+        SearchQuery::And(Box::new(lhs), Box::new(rhs))
+    }
+
+    fn or(lhs: SearchQuery, rhs: SearchQuery) -> SearchQuery {
+        SearchQuery::Or(Box::new(lhs), Box::new(rhs))
+    }
+
+    fn not(query: SearchQuery) -> SearchQuery {
+        SearchQuery::Not(Box::new(query))
+    }
+
+    fn parse(str: &str) -> SearchQuery {
+        SearchQuery::parse(str).unwrap()
+    }
+
+    fn parse_err(str: &str) -> &'static str {
+        SearchQuery::parse(str).unwrap_err().0
+    }
+
+    #[test]
+    fn parses_terms_and_fields() {
+        assert_eq!(parse("Rust"), term(Field::Any, "rust"));
+        assert_eq!(parse("title:Release"), term(Field::Title, "release"));
+        assert_eq!(parse("AUTHOR:jane"), term(Field::Author, "jane"));
+        // unknown prefixes are part of the term
+        assert_eq!(parse("foo:bar"), term(Field::Any, "foo:bar"));
+        assert_eq!(
+            parse(r#"tag:"release notes" feed:"a (b)""#),
+            and(term(Field::Tag, "release notes"), term(Field::Feed, "a (b)"))
+        );
+    }
+
+    #[test]
+    fn gives_and_precedence_over_or() {
+        assert_eq!(
+            parse("a OR b AND c"),
+            or(term(Field::Any, "a"), and(term(Field::Any, "b"), term(Field::Any, "c")))
+        );
+        assert_eq!(
+            parse("a b OR c"),
+            or(and(term(Field::Any, "a"), term(Field::Any, "b")), term(Field::Any, "c"))
+        );
+        assert_eq!(
+            parse("(a OR b) c"),
+            and(or(term(Field::Any, "a"), term(Field::Any, "b")), term(Field::Any, "c"))
+        );
+    }
+
+    #[test]
+    fn parses_negations() {
+        assert_eq!(parse("-a"), not(term(Field::Any, "a")));
+        assert_eq!(
+            parse("a NOT (b OR c)"),
+            and(
+                term(Field::Any, "a"),
+                not(or(term(Field::Any, "b"), term(Field::Any, "c")))
+            )
+        );
+        assert_eq!(parse("NOT -a"), not(not(term(Field::Any, "a"))));
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        assert_eq!(parse_err(""), "unexpected end of query");
+        assert_eq!(parse_err("(a"), "unbalanced parentheses");
+        assert_eq!(parse_err("a)"), "unbalanced parentheses");
+        assert_eq!(parse_err("a AND"), "unexpected end of query");
+        assert_eq!(parse_err("OR a"), "unexpected token");
+        assert_eq!(parse_err("title:"), "empty search term");
+        assert_eq!(parse_err(r#"title:"open"#), "unterminated phrase");
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(SearchQuery::parse(&nested(MAX_QUERY_DEPTH)).is_ok());
+        assert_eq!(parse_err(&nested(MAX_QUERY_DEPTH + 1)), "query is nested too deeply");
+        assert_eq!(parse_err(&"(".repeat(100_000)), "query is too long");
+        assert_eq!(parse_err(&"NOT ".repeat(MAX_QUERY_DEPTH + 1)), "query is nested too deeply");
+        assert_eq!(parse_err(&"a ".repeat(MAX_QUERY_TOKENS + 1)), "query is too long");
+    }
+
+    #[test]
+    fn matches_documents() {
+        let tags = ["news".to_owned()];
+        let doc = Document::new(
+            Some("Rust 1.0 Released"),
+            &[Some("<p>The release notes</p>"), None],
+            Some("Jane"),
+            "blog",
+            &tags,
+        );
+        assert!(parse("rust").matches(&doc));
+        assert!(parse("title:released -author:john").matches(&doc));
+        assert!(parse(r#"content:"release notes" tag:news feed:blog"#).matches(&doc));
+        assert!(!parse("tag:new").matches(&doc));
+        assert!(!parse("author:jane AND -rust").matches(&doc));
+        assert!(!parse("missing AND jane").matches(&doc));
+    }
+
+    #[test]
+    fn splits_index_terms() {
+        let terms = index_terms("Hello, WORLD! a 42 hello");
+        assert_eq!(terms.into_iter().collect::<Vec<_>>(), ["42", "hello", "world"]);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -5,29 +6,87 @@ use std::sync::Arc;
 use axum::body::Body;
 use axum::extract::{FromRequest, Path, Query, RequestParts};
 use axum::handler::Handler;
-use axum::http::{Method, Request, StatusCode};
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE, HOST};
+use axum::http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
 use axum::response::{Html, IntoResponse, Response};
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, patch, post};
 use axum::{async_trait, Extension, Json, Router};
-use rsst::client::{RssClient, RssRequest};
 use serde::{Deserialize, Deserializer};
+use sled_bincode::Value;
 use time::OffsetDateTime;
-use tower_http::auth::RequireAuthorizationLayer;
+use tower_http::auth::AsyncRequireAuthorizationLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
-use crate::refresh::{refresh_all_feeds, refresh_feed};
-use crate::repo::Repo;
+use crate::auth::{self, UserAuth};
+use crate::digest::random_token;
+use crate::discovery::discover_feeds;
+use crate::extract::fetch_article;
+use crate::fetch::HttpClient;
+use crate::refresh::{add_feed, refresh_all_feeds};
+use crate::repo::{FeedEntry, Repo, Store};
 use crate::result::{Result, ServiceEror};
-use crate::types::{EntryId, FeedId, Subscription, Tagging, TaggingId};
-use crate::AppConfig;
+use crate::search::{SearchFilter, SearchQuery};
+use crate::types::{
+    ApiTokenView, Entry, EntryExtras, EntryId, EntryView, ExtractedArticle, FeedId, FeedOptions, FeedSource, IconLink, SavedSearch, SavedSearchId, SharedFeed, Subscription, Tagging, TaggingId, TokenScope, UserView
+};
+use crate::{atom, codecs, fever, greader, miniflux, nextcloud, AppConfig};
 
-pub async fn run(repo: Arc<Repo>, config: &AppConfig) {
+const SEARCH_PAGE_SIZE: usize = 100;
+const ATOM_FEED_SIZE: usize = 50;
+
+pub async fn run(store: Arc<Store>, config: &AppConfig) {
+    tracing::info!("starting a server on port {}", config.port);
+    axum::Server::bind(&([0, 0, 0, 0], config.port).into())
+        .serve(router(store).into_make_service())
+        .await
+        .expect("http server failed")
+}
+
+fn router(store: Arc<Store>) -> Router {
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         .allow_origin(Any);
 
-    let admin_api = Router::new().route("/jobs/refresh", post(refresh_subscriptions));
+    // per-user settings can be managed with the account password or with write tokens,
+    // they're kept apart from the admin endpoints that manage the whole instance
+    let settings_api = Router::new()
+        .route(
+            "/subscriptions/:id/options",
+            get(get_feed_options).put(update_feed_options),
+        )
+        .route("/shared_feeds", get(get_shared_feeds).post(create_shared_feed))
+        .route("/shared_feeds/:token", delete(delete_shared_feed))
+        .layer(AsyncRequireAuthorizationLayer::new(UserAuth::with_tokens(
+            store.clone(),
+            TokenScope::Write,
+        )));
+
+    // tokens can't be used to issue other tokens
+    let tokens_api = Router::new()
+        .route("/tokens", get(get_api_tokens).post(create_api_token))
+        .route("/tokens/:id", delete(delete_api_token))
+        .layer(AsyncRequireAuthorizationLayer::new(UserAuth::basic(store.clone())));
+
+    let admin_api = Router::new()
+        .route("/jobs/refresh", post(refresh_subscriptions))
+        .route("/users", get(get_users).post(create_user))
+        .route("/users/:name", patch(update_user).delete(delete_user))
+        .layer(AsyncRequireAuthorizationLayer::new(UserAuth::with_tokens(
+            store.clone(),
+            TokenScope::Admin,
+        )));
+
+    let atom_api = Router::new()
+        .route("/starred", get(get_starred_atom))
+        .route("/tags/:name", get(get_tag_atom))
+        .route("/saved_searches/:id", get(get_saved_search_atom));
 
     let feedbin_api = Router::new()
         .route("/authentication.json", get(authenticate))
@@ -35,7 +94,13 @@ pub async fn run(repo: Arc<Repo>, config: &AppConfig) {
             "/subscriptions.json",
             get(get_subscriptions).post(add_subscription),
         )
-        .route("/subscriptions/:id.json", delete(delete_subscription))
+        .route(
+            "/subscriptions/:id",
+            get(get_subscription)
+                .patch(update_subscription)
+                .delete(delete_subscription),
+        )
+        .route("/subscriptions/:id/update.json", post(update_subscription))
         .route(
             "/unread_entries.json",
             get(get_unread).post(post_unread).delete(delete_unread),
@@ -44,25 +109,55 @@ pub async fn run(repo: Arc<Repo>, config: &AppConfig) {
             "/starred_entries.json",
             get(get_starred).post(post_starred).delete(delete_starred),
         )
+        .route(
+            "/recently_read_entries.json",
+            get(get_recently_read).post(post_recently_read),
+        )
         .route("/entries.json", get(get_entries))
+        .route("/search.json", get(search_entries))
         .route("/taggings.json", get(get_taggings).post(create_tagging))
-        .route("/taggings/:id.json", delete(delete_tagging));
+        .route("/taggings/:id.json", delete(delete_tagging))
+        .route("/tags.json", get(get_tags).post(rename_tag).delete(delete_tag))
+        .route(
+            "/saved_searches.json",
+            get(get_saved_searches).post(create_saved_search),
+        )
+        .route(
+            "/saved_searches/:id",
+            get(get_saved_search)
+                .patch(update_saved_search)
+                .delete(delete_saved_search),
+        )
+        .route("/saved_searches/:id/update.json", post(update_saved_search))
+        .route("/icons.json", get(get_icons))
+        .route("/pages.json", post(create_page))
+        .layer(AsyncRequireAuthorizationLayer::new(UserAuth::with_tokens(
+            store.clone(),
+            TokenScope::Write,
+        )));
+
+    let authorized = Router::new()
+        .nest("/atom", atom_api)
+        .nest("/miniflux", miniflux::router())
+        .nest("/nextcloud", nextcloud::router())
+        .route("/webui", get(get_webui))
+        .route("/extract/:id", get(get_extracted_content))
+        .route("/icons/:host", get(get_icon))
+        .layer(AsyncRequireAuthorizationLayer::new(UserAuth::basic(store.clone())));
 
-    let app = Router::new()
+    // the Fever and Google Reader APIs have their own authentication schemes
+    Router::new()
+        .route("/atom/shared/:token", get(get_shared_atom))
+        .nest("/fever", fever::router())
+        .nest("/greader", greader::router())
         .nest("/admin", admin_api)
+        .nest("/settings", settings_api.merge(tokens_api))
         .nest("/feedbin", feedbin_api)
-        .route("/webui", get(get_webui))
+        .merge(authorized)
         .fallback(fallback.into_service())
         .layer(TraceLayer::new_for_http())
-        .layer(RequireAuthorizationLayer::basic(&config.user, &config.password))
         .layer(cors)
-        .layer(Extension(repo.clone()));
-
-    tracing::info!("starting a server on port {}", config.port);
-    axum::Server::bind(&([0, 0, 0, 0], config.port).into())
-        .serve(app.into_make_service())
-        .await
-        .expect("http server failed")
+        .layer(Extension(store))
 }
 
 async fn get_webui() -> impl IntoResponse {
@@ -74,8 +169,24 @@ async fn authenticate() -> StatusCode {
     StatusCode::OK
 }
 
-async fn get_subscriptions(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
-    repo.get_subscriptions().map(Json)
+async fn get_subscriptions(
+    Extension(repo): Extension<Arc<Repo>>,
+    Query(query): Query<SubscriptionsQuery>,
+) -> impl IntoResponse {
+    match query.since {
+        Some(since) => repo.get_subscriptions_since(since).map(Json),
+        None => repo.get_subscriptions().map(Json),
+    }
+}
+
+async fn get_subscription(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(feed_id): PathWithExt<FeedId>,
+) -> Result<Response, ServiceEror> {
+    match repo.get_subscription(feed_id)? {
+        Some(sub) => Ok(Json(sub).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
 }
 
 async fn get_unread(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
@@ -86,8 +197,7 @@ async fn post_unread(
     Extension(repo): Extension<Arc<Repo>>,
     Json(entries): Json<UnreadEntries>,
 ) -> impl IntoResponse {
-    repo.add_unread(entries.unread_entries.iter().copied())?;
-    Ok::<_, ServiceEror>(Json(entries.unread_entries))
+    repo.add_unread(entries.unread_entries).map(Json)
 }
 
 async fn delete_unread(
@@ -97,6 +207,17 @@ async fn delete_unread(
     repo.delete_unread(entries.unread_entries)
 }
 
+async fn get_recently_read(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
+    repo.get_recently_read().map(Json)
+}
+
+async fn post_recently_read(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(entries): Json<RecentlyReadEntries>,
+) -> impl IntoResponse {
+    repo.add_recently_read(entries.recently_read_entries).map(Json)
+}
+
 async fn get_starred(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
     repo.get_starred().map(Json)
 }
@@ -105,8 +226,7 @@ async fn post_starred(
     Extension(repo): Extension<Arc<Repo>>,
     Json(entries): Json<StarredEntries>,
 ) -> impl IntoResponse {
-    repo.add_starred(entries.starred_entries.iter().copied())?;
-    Ok::<_, ServiceEror>(Json(entries.starred_entries))
+    repo.add_starred(entries.starred_entries).map(Json)
 }
 
 async fn delete_starred(
@@ -119,30 +239,90 @@ async fn delete_starred(
 async fn get_entries(
     Extension(repo): Extension<Arc<Repo>>,
     Query(query): Query<EntriesQuery>,
-) -> impl IntoResponse {
-    if let Some(true) = query.starred {
-        repo.get_starred_entries(query.page, query.per_page).map(Json)
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let entries = if let Some(true) = query.starred {
+        repo.get_starred_entries(query.page, query.per_page)?
     } else {
-        repo.get_entries(query.page, query.per_page, &query.tags)
-            .map(Json)
-    }
+        repo.get_entries(query.page, query.per_page, &query.tags)?
+    };
+
+    let extended = query.mode.as_deref() == Some("extended");
+    let options = EntryOptions {
+        include_enclosure: extended || query.include_enclosure == Some(true),
+        include_original: extended || query.include_original == Some(true),
+    };
+    render_entries(&repo, &entries, &get_base_url(&headers), options)
+}
+
+async fn search_entries(
+    Extension(repo): Extension<Arc<Repo>>,
+    Query(query): Query<SearchEntriesQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let filter = SearchFilter {
+        feed_id: query.feed_id,
+        tags: query.tags,
+        read: query.read,
+        starred: query.starred,
+    };
+    let per_page = query.per_page.unwrap_or(SEARCH_PAGE_SIZE);
+    let entries = repo.search_entries_by_text(&query.query, &filter, query.page.unwrap_or(1), per_page)?;
+    render_entries(&repo, &entries, &get_base_url(&headers), EntryOptions::default())
 }
 
 async fn add_subscription(
     Extension(repo): Extension<Arc<Repo>>,
     Json(add_sub): Json<AddSubscription>,
 ) -> Result<Response, ServiceEror> {
-    let created_at = OffsetDateTime::now_utc();
-    let feed = RssClient::default()
-        .exec(RssRequest::new(&add_sub.feed_url)?)
-        .await?;
-    let id = repo.new_feed_id()?;
-    let sub = Subscription::from_feed(id, feed.borrow_feed(), &add_sub.feed_url, created_at);
-    repo.add_subscription(&sub)?;
-    refresh_feed(&repo, id, feed.borrow_feed())?;
+    let url = match add_sub.feed_url.trim() {
+        url if url.contains("://") => url.to_owned(),
+        // users tend to paste bare domains like `example.com`
+        url => format!("https://{url}"),
+    };
+    if let Some(resp) = subscribe(&repo, &url).await? {
+        return Ok(resp);
+    }
+
+    let links = discover_feeds(&HttpClient::default(), &url)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::info!("feed discovery failed for {url}: {err}");
+            vec![]
+        });
+    match links.as_slice() {
+        [] => Ok(StatusCode::NOT_FOUND.into_response()),
+        [link] => match subscribe(&repo, &link.feed_url).await? {
+            Some(resp) => Ok(resp),
+            None => Ok(StatusCode::NOT_FOUND.into_response()),
+        },
+        _ => Ok((StatusCode::MULTIPLE_CHOICES, Json(links)).into_response()),
+    }
+}
 
-    tracing::info!("successfully added a subscription for {}", sub.feed_url);
-    Ok((StatusCode::CREATED, Json(sub)).into_response())
+// returns `None` when the URL does not point at a valid feed
+async fn subscribe(repo: &Repo, feed_url: &str) -> Result<Option<Response>, ServiceEror> {
+    if let Some(sub) = repo.get_subscription_by_url(feed_url)? {
+        return Ok(Some((StatusCode::FOUND, Json(sub)).into_response()));
+    }
+
+    match add_feed(repo, feed_url).await? {
+        Some(id) => Ok(repo
+            .get_subscription(id)?
+            .map(|sub| (StatusCode::CREATED, Json(sub)).into_response())),
+        None => Ok(None),
+    }
+}
+
+async fn update_subscription(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(feed_id): PathWithExt<FeedId>,
+    Json(update_sub): Json<UpdateSubscription>,
+) -> Result<Response, ServiceEror> {
+    match repo.rename_subscription(feed_id, &update_sub.title)? {
+        Some(sub) => Ok(Json(sub).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
 }
 
 async fn delete_subscription(
@@ -152,8 +332,76 @@ async fn delete_subscription(
     repo.delete_subscription(feed_id)
 }
 
+async fn get_feed_options(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+) -> Result<Response, ServiceEror> {
+    if repo.get_subscription(feed_id)?.is_none() {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    Ok(Json(repo.get_feed_options(feed_id)?).into_response())
+}
+
+async fn update_feed_options(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(feed_id): Path<FeedId>,
+    Json(options): Json<FeedOptions>,
+) -> Result<Response, ServiceEror> {
+    if repo.get_subscription(feed_id)?.is_none() {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    repo.set_feed_options(feed_id, &options)?;
+    Ok(Json(options).into_response())
+}
+
 async fn refresh_subscriptions(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
-    refresh_all_feeds(&repo).await
+    refresh_all_feeds(repo.store()).await
+}
+
+async fn create_page(
+    Extension(repo): Extension<Arc<Repo>>,
+    headers: HeaderMap,
+    Json(add_page): Json<AddPage>,
+) -> Result<Response, ServiceEror> {
+    let created_at = OffsetDateTime::now_utc();
+    if repo.get_subscription(FeedId::PAGES)?.is_none() {
+        repo.add_subscription(&Subscription::pages(created_at))?;
+    }
+
+    // the page is saved even if it can't be retrieved
+    let article = match fetch_article(&HttpClient::default(), &add_page.url).await {
+        Ok(article) => article,
+        Err(err) => {
+            tracing::info!("failed to retrieve page {}: {err}", add_page.url);
+            None
+        }
+    };
+    let title = add_page
+        .title
+        .as_deref()
+        .or_else(|| article.as_ref().and_then(|article| article.title.as_deref()))
+        .unwrap_or(&add_page.url);
+    let content = article.as_ref().map(|article| article.content.as_str());
+    let mut entry = Entry::from_page(&add_page.url, Some(title), content, created_at);
+    entry.author = article.as_ref().and_then(|article| article.author.as_deref());
+
+    if let Some(article) = &article {
+        repo.add_article(entry.id, &ExtractedArticle::from_article(article))?;
+    }
+    let id = entry.id;
+    let extras = EntryExtras::from_entry(&entry);
+    repo.add_page(entry, extras)?;
+    repo.add_starred([id])?;
+    tracing::info!("saved page {}", add_page.url);
+
+    match repo.get_entry(id)? {
+        Some(res) => {
+            let entry = res.value()?;
+            let view = EntryView::new(&entry, &get_base_url(&headers));
+            Ok((StatusCode::CREATED, Json(view)).into_response())
+        }
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
 }
 
 async fn get_taggings(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
@@ -164,10 +412,19 @@ async fn create_tagging(
     Extension(repo): Extension<Arc<Repo>>,
     Json(add_tagging): Json<AddTagging>,
 ) -> Result<Response, ServiceEror> {
+    if repo.get_subscription(add_tagging.feed_id)?.is_none() {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, "unknown feed").into_response());
+    }
+
     let id = repo.new_tagging_id()?;
     let tagging = Tagging::new(id, add_tagging.feed_id, &add_tagging.name);
-    repo.add_tagging(&tagging)?;
-    Ok((StatusCode::CREATED, Json(tagging)).into_response())
+    match repo.add_tagging(&tagging)? {
+        Some(existing) => match repo.get_tagging(existing)? {
+            Some(existing) => Ok((StatusCode::FOUND, Json(existing)).into_response()),
+            None => Ok(StatusCode::CONFLICT.into_response()),
+        },
+        None => Ok((StatusCode::CREATED, Json(tagging)).into_response()),
+    }
 }
 
 async fn delete_tagging(
@@ -177,6 +434,345 @@ async fn delete_tagging(
     repo.delete_tagging(tagging_id)
 }
 
+async fn get_tags(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
+    repo.get_tags().map(Json)
+}
+
+async fn rename_tag(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(rename): Json<RenameTag>,
+) -> impl IntoResponse {
+    repo.rename_tag(&rename.old_name, &rename.new_name)?;
+    repo.get_taggings().map(Json)
+}
+
+async fn delete_tag(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(tag): Json<DeleteTag>,
+) -> impl IntoResponse {
+    repo.delete_tag(&tag.name)?;
+    repo.get_taggings().map(Json)
+}
+
+async fn get_saved_searches(Extension(repo): Extension<Arc<Repo>>) -> impl IntoResponse {
+    repo.get_saved_searches().map(Json)
+}
+
+async fn get_saved_search(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(search_id): PathWithExt<SavedSearchId>,
+    Query(query): Query<SavedSearchQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let search = match repo.get_saved_search(search_id)? {
+        Some(search) => search,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let search = SearchQuery::parse(search.value()?.query)?;
+
+    if let Some(true) = query.include_entries {
+        let entries = repo.search_entries(&search, query.page.unwrap_or(1), SEARCH_PAGE_SIZE)?;
+        render_entries(&repo, &entries, &get_base_url(&headers), EntryOptions::default())
+    } else {
+        Ok(Json(repo.search_entry_ids(&search)?).into_response())
+    }
+}
+
+async fn create_saved_search(
+    Extension(repo): Extension<Arc<Repo>>,
+    Json(add_search): Json<AddSavedSearch>,
+) -> Result<Response, ServiceEror> {
+    SearchQuery::parse(&add_search.query)?;
+
+    let id = repo.new_saved_search_id()?;
+    let search = SavedSearch::new(id, &add_search.name, &add_search.query);
+    repo.add_saved_search(&search)?;
+    Ok((StatusCode::CREATED, Json(search)).into_response())
+}
+
+async fn update_saved_search(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(search_id): PathWithExt<SavedSearchId>,
+    Json(update_search): Json<UpdateSavedSearch>,
+) -> Result<Response, ServiceEror> {
+    if let Some(query) = &update_search.query {
+        SearchQuery::parse(query)?;
+    }
+
+    let name = update_search.name.as_deref();
+    let query = update_search.query.as_deref();
+    match repo.update_saved_search(search_id, name, query)? {
+        Some(search) => Ok(Json(search).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+async fn delete_saved_search(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(search_id): PathWithExt<SavedSearchId>,
+) -> impl IntoResponse {
+    repo.delete_saved_search(search_id)
+}
+
+async fn get_icons(Extension(repo): Extension<Arc<Repo>>, headers: HeaderMap) -> impl IntoResponse {
+    let base_url = get_base_url(&headers);
+    let res = repo
+        .get_icon_hosts()?
+        .into_iter()
+        .map(|host| IconLink {
+            url: format!("{base_url}/icons/{host}"),
+            host,
+        })
+        .collect::<Vec<_>>();
+    Ok::<_, ServiceEror>(Json(res))
+}
+
+async fn get_icon(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(host): Path<String>,
+) -> Result<Response, ServiceEror> {
+    let icon = match repo.get_icon(&host)? {
+        Some(icon) => icon,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let icon = icon.value()?;
+    let headers = [
+        (CONTENT_TYPE, icon.content_type.to_owned()),
+        (CACHE_CONTROL, "max-age=86400".to_owned()),
+    ];
+    Ok((headers, icon.data.to_vec()).into_response())
+}
+
+// only entries the user can read are extracted, other entries are reported as missing
+async fn get_extracted_content(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(entry_id): PathWithExt<EntryId>,
+) -> Result<Response, ServiceEror> {
+    let entry = match repo.get_entry(entry_id)? {
+        Some(entry) => entry,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    if let Some(article) = repo.get_article(entry_id)? {
+        return Ok(Json(article).into_response());
+    }
+
+    let url = match entry.value()?.url {
+        Some(url) => url,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    match fetch_article(&HttpClient::default(), url).await? {
+        Some(article) => {
+            let article = ExtractedArticle::from_article(&article);
+            repo.add_article(entry_id, &article)?;
+            Ok(Json(article).into_response())
+        }
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+async fn get_starred_atom(
+    Extension(repo): Extension<Arc<Repo>>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let self_url = format!("{}/atom/starred", get_base_url(&headers));
+    render_atom_feed(&repo, &FeedSource::Starred, &self_url)
+}
+
+async fn get_tag_atom(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let self_url = format!("{}/atom/tags/{name}", get_base_url(&headers));
+    render_atom_feed(&repo, &FeedSource::Tag(name), &self_url)
+}
+
+async fn get_saved_search_atom(
+    Extension(repo): Extension<Arc<Repo>>,
+    PathWithExt(search_id): PathWithExt<SavedSearchId>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let self_url = format!("{}/atom/saved_searches/{search_id}", get_base_url(&headers));
+    render_atom_feed(&repo, &FeedSource::SavedSearch(search_id), &self_url)
+}
+
+async fn get_shared_atom(
+    Extension(store): Extension<Arc<Store>>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    match store.get_shared_feed(&token)? {
+        Some((repo, source)) => render_atom_feed(&repo, &source, &shared_feed_url(&headers, &token)),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+async fn get_shared_feeds(
+    Extension(repo): Extension<Arc<Repo>>,
+    headers: HeaderMap,
+) -> Result<Response, ServiceEror> {
+    let res = repo
+        .get_shared_feeds()?
+        .into_iter()
+        .map(|(token, source)| SharedFeed {
+            url: shared_feed_url(&headers, &token),
+            token,
+            source,
+        })
+        .collect::<Vec<_>>();
+    Ok(Json(res).into_response())
+}
+
+async fn create_shared_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    headers: HeaderMap,
+    Json(add_feed): Json<AddSharedFeed>,
+) -> Result<Response, ServiceEror> {
+    if let FeedSource::SavedSearch(id) = add_feed.source {
+        if repo.get_saved_search(id)?.is_none() {
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+    }
+
+    let token = random_token()?;
+    repo.add_shared_feed(&token, &add_feed.source)?;
+    let feed = SharedFeed {
+        url: shared_feed_url(&headers, &token),
+        token,
+        source: add_feed.source,
+    };
+    Ok((StatusCode::CREATED, Json(feed)).into_response())
+}
+
+async fn delete_shared_feed(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(token): Path<String>,
+) -> Result<StatusCode, ServiceEror> {
+    if repo.delete_shared_feed(&token)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
+}
+
+async fn get_users(Extension(store): Extension<Arc<Store>>) -> Result<Response, ServiceEror> {
+    let accounts = store.get_accounts()?;
+    let res = accounts
+        .iter()
+        .map(|res| {
+            let account = res.value()?;
+            let admin = store.get_admin_password(account.name)?.is_some();
+            Ok(UserView::new(&account, admin))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Json(res).into_response())
+}
+
+async fn create_user(
+    Extension(store): Extension<Arc<Store>>,
+    Json(add_user): Json<AddUser>,
+) -> Result<Response, ServiceEror> {
+    if add_user.name.is_empty() || add_user.password.is_empty() {
+        return Ok(StatusCode::UNPROCESSABLE_ENTITY.into_response());
+    }
+    if !auth::create_user(&store, &add_user.name, &add_user.password)? {
+        return Ok(StatusCode::CONFLICT.into_response());
+    }
+    render_user(&store, &add_user.name, StatusCode::CREATED)
+}
+
+async fn update_user(
+    Extension(store): Extension<Arc<Store>>,
+    Path(name): Path<String>,
+    Json(update_user): Json<UpdateUser>,
+) -> Result<Response, ServiceEror> {
+    if store.get_account(&name)?.is_none() {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    if let Some(password) = &update_user.password {
+        if password.is_empty() {
+            return Ok(StatusCode::UNPROCESSABLE_ENTITY.into_response());
+        }
+        auth::set_password(&store, &name, password)?;
+    }
+    if let Some(disabled) = update_user.disabled {
+        auth::set_disabled(&store, &name, disabled)?;
+    }
+    if let Some(password) = &update_user.admin_password {
+        if password.is_empty() {
+            return Ok(StatusCode::UNPROCESSABLE_ENTITY.into_response());
+        }
+        auth::set_admin_password(&store, &name, password)?;
+    }
+    if update_user.admin == Some(false) {
+        store.delete_admin_password(&name)?;
+    }
+    render_user(&store, &name, StatusCode::OK)
+}
+
+async fn delete_user(
+    Extension(store): Extension<Arc<Store>>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ServiceEror> {
+    if store.delete_user(&name)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
+}
+
+async fn get_api_tokens(Extension(repo): Extension<Arc<Repo>>) -> Result<Response, ServiceEror> {
+    let tokens = repo.store().get_api_tokens(repo.user_name())?;
+    let res = tokens
+        .iter()
+        .map(|(id, res)| Ok(ApiTokenView::new(id, &res.value()?)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Json(res).into_response())
+}
+
+// tokens can't grant more than the credentials they were created with
+async fn create_api_token(
+    Extension(repo): Extension<Arc<Repo>>,
+    Extension(granted): Extension<TokenScope>,
+    Json(add_token): Json<AddApiToken>,
+) -> Result<Response, ServiceEror> {
+    if add_token.name.is_empty() {
+        return Ok(StatusCode::UNPROCESSABLE_ENTITY.into_response());
+    }
+    if add_token.scope > granted {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let store = repo.store();
+    let (id, token) = auth::create_api_token(store, repo.user_name(), &add_token.name, add_token.scope)?;
+    match store.get_api_token(&id)? {
+        Some(res) => {
+            let view = ApiTokenView {
+                token: Some(token),
+                ..ApiTokenView::new(&id, &res.value()?)
+            };
+            Ok((StatusCode::CREATED, Json(view)).into_response())
+        }
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+// tokens of other users are treated as missing
+async fn delete_api_token(
+    Extension(repo): Extension<Arc<Repo>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ServiceEror> {
+    let store = repo.store();
+    let owned = match store.get_api_token(&id)? {
+        Some(res) => res.value()?.user == repo.user_name(),
+        None => false,
+    };
+    if owned && store.delete_api_token(&id)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
+}
+
 async fn fallback(req: Request<Body>) -> impl IntoResponse {
     tracing::info!("request not matched: {}", req.uri());
     StatusCode::NOT_FOUND
@@ -187,8 +783,29 @@ struct EntriesQuery {
     page: usize,
     per_page: usize,
     starred: Option<bool>,
+    mode: Option<String>,
+    include_enclosure: Option<bool>,
+    include_original: Option<bool>,
+    #[serde(deserialize_with = "deserialize_qs_array", default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchEntriesQuery {
+    query: String,
+    page: Option<usize>,
+    per_page: Option<usize>,
+    feed_id: Option<FeedId>,
     #[serde(deserialize_with = "deserialize_qs_array", default)]
     tags: Vec<String>,
+    read: Option<bool>,
+    starred: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionsQuery {
+    #[serde(with = "codecs::rfc3339_date_opt", default)]
+    since: Option<OffsetDateTime>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -196,22 +813,98 @@ struct AddSubscription {
     feed_url: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct UpdateSubscription {
+    title: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct UnreadEntries {
     unread_entries: Vec<EntryId>,
 }
 
+#[derive(Debug, Deserialize)]
+struct RecentlyReadEntries {
+    recently_read_entries: Vec<EntryId>,
+}
+
 #[derive(Debug, Deserialize)]
 struct StarredEntries {
     starred_entries: Vec<EntryId>,
 }
 
+#[derive(Debug, Deserialize)]
+struct AddPage {
+    url: String,
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddUser {
+    name: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateUser {
+    password: Option<String>,
+    disabled: Option<bool>,
+    admin_password: Option<String>,
+    admin: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddApiToken {
+    name: String,
+    scope: TokenScope,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddSharedFeed {
+    source: FeedSource,
+}
+
 #[derive(Debug, Deserialize)]
 struct AddTagging {
     feed_id: FeedId,
     name: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct RenameTag {
+    old_name: String,
+    new_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteTag {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavedSearchQuery {
+    include_entries: Option<bool>,
+    page: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddSavedSearch {
+    name: String,
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateSavedSearch {
+    name: Option<String>,
+    query: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct EntryOptions {
+    include_enclosure: bool,
+    include_original: bool,
+}
+
 struct PathWithExt<A>(A);
 
 #[async_trait]
@@ -223,16 +916,115 @@ impl<A: FromStr, B: Send> FromRequest<B> for PathWithExt<A> {
             .extract::<Path<String>>()
             .await
             .map_err(|_| (StatusCode::BAD_REQUEST, "could not extract path parameter"))?;
-        match str.split_once('.') {
-            Some((str, _)) => str
-                .parse()
-                .map(PathWithExt)
-                .map_err(|_| (StatusCode::BAD_REQUEST, "could not parse path parameter")),
-            None => return Err((StatusCode::BAD_REQUEST, "missing path parameter")),
+        // the extension is optional for nested routes like `/subscriptions/:id/update.json`
+        let str = str.split_once('.').map_or(str.as_str(), |(str, _)| str);
+        if str.is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "missing path parameter"));
+        }
+        str.parse()
+            .map(PathWithExt)
+            .map_err(|_| (StatusCode::BAD_REQUEST, "could not parse path parameter"))
+    }
+}
+
+fn render_entries(
+    repo: &Repo,
+    entries: &[Value<FeedEntry>],
+    base_url: &str,
+    options: EntryOptions,
+) -> Result<Response, ServiceEror> {
+    let entries = entries
+        .iter()
+        .map(|res| res.value())
+        .collect::<Result<Vec<_>, _>>()?;
+    let extras = if options.include_enclosure || options.include_original {
+        entries
+            .iter()
+            .map(|entry| repo.get_entry_extras(entry.id))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        vec![]
+    };
+    let extras = extras
+        .iter()
+        .map(|res| res.as_ref().map(|res| res.value()).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let res = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mut view = EntryView::new(entry, base_url);
+            if let Some(Some(extras)) = extras.get(i) {
+                if options.include_enclosure {
+                    view.enclosure = extras.enclosure.as_ref();
+                }
+                if options.include_original {
+                    view.original = Some(extras.original.with_content(entry));
+                }
+            }
+            view
+        })
+        .collect::<Vec<_>>();
+    Ok(Json(res).into_response())
+}
+
+fn render_atom_feed(repo: &Repo, source: &FeedSource, self_url: &str) -> Result<Response, ServiceEror> {
+    let (title, entries) = match source {
+        FeedSource::Starred => ("Starred".to_owned(), repo.get_starred_entries(1, ATOM_FEED_SIZE)?),
+        FeedSource::Tag(name) => {
+            let entries = repo.get_entries(1, ATOM_FEED_SIZE, std::slice::from_ref(name))?;
+            (name.clone(), entries)
+        }
+        FeedSource::SavedSearch(id) => {
+            let search = match repo.get_saved_search(*id)? {
+                Some(search) => search,
+                None => return Ok(StatusCode::NOT_FOUND.into_response()),
+            };
+            let search = search.value()?;
+            let entries = repo.search_entries(&SearchQuery::parse(search.query)?, 1, ATOM_FEED_SIZE)?;
+            (search.name.to_owned(), entries)
+        }
+    };
+    let entries = entries
+        .iter()
+        .map(|res| res.value())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut feeds = HashMap::new();
+    for sub in repo.get_subscriptions()? {
+        let sub = sub.value()?;
+        feeds.insert(sub.feed_id, sub.title.to_owned());
+    }
+
+    let headers = [(CONTENT_TYPE, "application/atom+xml; charset=utf-8")];
+    Ok((headers, atom::render_feed(&title, self_url, &entries, &feeds)).into_response())
+}
+
+fn shared_feed_url(headers: &HeaderMap, token: &str) -> String {
+    format!("{}/atom/shared/{token}", get_base_url(headers))
+}
+
+fn render_user(store: &Store, name: &str, status: StatusCode) -> Result<Response, ServiceEror> {
+    match store.get_account(name)? {
+        Some(res) => {
+            let admin = store.get_admin_password(name)?.is_some();
+            Ok((status, Json(UserView::new(&res.value()?, admin))).into_response())
         }
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
     }
 }
 
+// the URL the service is reachable under, as seen by the client
+fn get_base_url(headers: &HeaderMap) -> String {
+    let header = |name| headers.get(name).and_then(|val: &HeaderValue| val.to_str().ok());
+    let scheme = header("x-forwarded-proto").unwrap_or("http");
+    let host = header("x-forwarded-host")
+        .or_else(|| header(HOST.as_str()))
+        .unwrap_or("localhost");
+    format!("{scheme}://{host}")
+}
+
 fn deserialize_qs_array<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
     D: Deserializer<'de>,
@@ -245,3 +1037,320 @@ where
         .collect::<Result<Vec<T>, T::Err>>()
         .map_err(serde::de::Error::custom)
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header::AUTHORIZATION;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::digest::md5_hex;
+
+    struct Instance {
+        _dir: tempfile::TempDir,
+        store: Arc<Store>,
+        app: Router,
+    }
+
+    impl Instance {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let store = Arc::new(Store::new(dir.path()).unwrap());
+            let app = router(store.clone());
+            Self { _dir: dir, store, app }
+        }
+
+        // creates a user with the password `{name}-password` subscribed to a feed with a single entry
+        fn add_user(&self, name: &str) -> EntryId {
+            auth::create_user(&self.store, name, &format!("{name}-password")).unwrap();
+            let repo = self.store.get_repo(name).unwrap().unwrap();
+            let feed_id = self.store.new_feed_id().unwrap();
+            let feed_url = format!("https://{name}.example.com/feed");
+            let now = OffsetDateTime::now_utc();
+            repo.add_subscription(&Subscription {
+                id: feed_id,
+                created_at: now,
+                feed_id,
+                title: name,
+                feed_url: &feed_url,
+                site_url: &feed_url,
+            })
+            .unwrap();
+            let url = format!("https://{name}.example.com/post");
+            let entry = Entry {
+                feed_id,
+                ..Entry::from_page(&url, Some("Post"), None, now)
+            };
+            let id = entry.id;
+            let extras = EntryExtras::from_entry(&entry);
+            self.store.insert_entry(entry, extras).unwrap();
+            id
+        }
+
+        fn add_page(&self, name: &str) -> EntryId {
+            let url = format!("https://{name}.example.com/page");
+            let entry = Entry::from_page(&url, Some("Page"), None, OffsetDateTime::now_utc());
+            let id = entry.id;
+            let extras = EntryExtras::from_entry(&entry);
+            self.repo(name).add_page(entry, extras).unwrap();
+            id
+        }
+
+        fn repo(&self, name: &str) -> Arc<Repo> {
+            self.store.get_repo(name).unwrap().unwrap()
+        }
+
+        // the number clients use to refer to the entry
+        fn seq(&self, id: EntryId) -> u64 {
+            self.repo("alice").get_entry_seq(id).unwrap().unwrap()
+        }
+
+        async fn send(&self, request: Request<Body>) -> (StatusCode, String) {
+            let res = self.app.clone().oneshot(request).await.unwrap();
+            let status = res.status();
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+
+        async fn get_json(&self, uri: &str, auth: &str) -> (StatusCode, serde_json::Value) {
+            let request = Request::get(uri).header(AUTHORIZATION, auth).body(Body::empty());
+            let (status, body) = self.send(request.unwrap()).await;
+            (status, serde_json::from_str(&body).unwrap_or_default())
+        }
+
+        async fn send_json(
+            &self,
+            method: Method,
+            uri: &str,
+            auth: &str,
+            body: serde_json::Value,
+        ) -> (StatusCode, serde_json::Value) {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, auth)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()));
+            let (status, body) = self.send(request.unwrap()).await;
+            (status, serde_json::from_str(&body).unwrap_or_default())
+        }
+    }
+
+    fn basic(user: &str, password: &str) -> String {
+        format!("Basic {}", base64::encode(format!("{user}:{password}")))
+    }
+
+    fn ids(items: &serde_json::Value) -> Vec<u64> {
+        let items = items.as_array().unwrap();
+        items.iter().map(|item| item["id"].as_u64().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn feedbin_only_exposes_the_data_of_the_user() {
+        let app = Instance::new();
+        let alice_entry = app.add_user("alice");
+        let bob_entry = app.add_user("bob");
+        let page = app.add_page("bob");
+        let alice = basic("alice", "alice-password");
+
+        let (status, subs) = app.get_json("/feedbin/subscriptions.json", &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(subs.as_array().unwrap().len(), 1);
+        assert_eq!(subs[0]["title"], "alice");
+        let (_, entries) = app.get_json("/feedbin/entries.json?page=1&per_page=10", &alice).await;
+        assert_eq!(entries.as_array().unwrap().len(), 1);
+        assert_eq!(entries[0]["id"], json!(alice_entry));
+
+        let starred = json!({ "starred_entries": [alice_entry, bob_entry, page] });
+        let (_, res) = app
+            .send_json(Method::POST, "/feedbin/starred_entries.json", &alice, starred)
+            .await;
+        assert_eq!(res, json!([alice_entry]));
+        let unread = json!({ "unread_entries": [bob_entry, page] });
+        let (_, res) = app
+            .send_json(Method::POST, "/feedbin/unread_entries.json", &alice, unread)
+            .await;
+        assert_eq!(res, json!([]));
+
+        let (_, starred) = app.get_json("/feedbin/starred_entries.json", &alice).await;
+        assert_eq!(starred, json!([alice_entry]));
+        let (_, unread) = app.get_json("/feedbin/unread_entries.json", &alice).await;
+        assert_eq!(unread, json!([alice_entry]));
+        let (_, unread) = app
+            .get_json("/feedbin/unread_entries.json", &basic("bob", "bob-password"))
+            .await;
+        assert_eq!(unread.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn tokens_only_grant_their_scope() {
+        let app = Instance::new();
+        app.add_user("alice");
+        let (_, read) = auth::create_api_token(&app.store, "alice", "read", TokenScope::Read).unwrap();
+        let (_, admin) = auth::create_api_token(&app.store, "alice", "admin", TokenScope::Admin).unwrap();
+        let (read, admin) = (format!("Bearer {read}"), format!("Bearer {admin}"));
+
+        let (status, _) = app.get_json("/feedbin/subscriptions.json", &read).await;
+        assert_eq!(status, StatusCode::OK);
+        let starred = json!({ "starred_entries": [] });
+        let (status, _) = app
+            .send_json(Method::POST, "/feedbin/starred_entries.json", &read, starred)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // tokens are not accepted by the reader APIs and can't issue other tokens
+        let (status, _) = app.get_json("/miniflux/v1/me", &admin).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.get_json("/settings/tokens", &admin).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // admin tokens only grant write access until the user has an admin password
+        let (status, _) = app.get_json("/settings/shared_feeds", &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.get_json("/admin/users", &admin).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        auth::set_admin_password(&app.store, "alice", "alice-admin").unwrap();
+        let (status, _) = app.get_json("/admin/users", &admin).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn admin_routes_require_the_admin_password() {
+        let app = Instance::new();
+        app.add_user("alice");
+        app.add_user("bob");
+        auth::set_admin_password(&app.store, "alice", "alice-admin").unwrap();
+        let (user, admin) = (basic("alice", "alice-password"), basic("alice", "alice-admin"));
+
+        let (status, _) = app.get_json("/admin/users", &user).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.get_json("/settings/tokens", &user).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.get_json("/admin/users", &admin).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, me) = app.get_json("/miniflux/v1/me", &user).await;
+        assert_eq!(me["is_admin"], false);
+        let (_, me) = app.get_json("/miniflux/v1/me", &admin).await;
+        assert_eq!(me["is_admin"], true);
+        let (_, other) = app.get_json("/miniflux/v1/me", &basic("bob", "bob-password")).await;
+        assert_ne!(me["id"], other["id"]);
+    }
+
+    #[tokio::test]
+    async fn changed_passwords_are_not_accepted_from_the_cache() {
+        let app = Instance::new();
+        app.add_user("alice");
+        let (old, new) = (basic("alice", "alice-password"), basic("alice", "changed"));
+
+        let (status, _) = app.get_json("/feedbin/authentication.json", &old).await;
+        assert_eq!(status, StatusCode::OK);
+        auth::set_password(&app.store, "alice", "changed").unwrap();
+        let (status, _) = app.get_json("/feedbin/authentication.json", &old).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.get_json("/feedbin/authentication.json", &new).await;
+        assert_eq!(status, StatusCode::OK);
+
+        auth::set_disabled(&app.store, "alice", true).unwrap();
+        let (status, _) = app.get_json("/feedbin/authentication.json", &new).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn fever_only_exposes_the_data_of_the_user() {
+        let app = Instance::new();
+        let alice_entry = app.add_user("alice");
+        let bob_entry = app.add_user("bob");
+        auth::set_fever_password(&app.store, "alice", "alice-fever").unwrap();
+        let key = md5_hex(b"alice:alice-fever");
+
+        let (_, res) = app.get_json("/fever?api&api_key=wrong&items", "").await;
+        assert_eq!(res["auth"], 0);
+        assert!(res.get("items").is_none());
+
+        let (_, res) = app.get_json(&format!("/fever?api&api_key={key}&items&feeds"), "").await;
+        assert_eq!(res["auth"], 1);
+        assert_eq!(ids(&res["items"]), vec![app.seq(alice_entry)]);
+        assert_eq!(res["total_items"], 1);
+        assert_eq!(res["feeds"].as_array().unwrap().len(), 1);
+
+        let seq = app.seq(bob_entry);
+        let uri = format!("/fever?api&api_key={key}&mark=item&as=saved&id={seq}&saved_item_ids");
+        let (_, res) = app.get_json(&uri, "").await;
+        assert_eq!(res["saved_item_ids"], "");
+        assert!(app.repo("alice").get_starred().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn greader_only_exposes_the_data_of_the_user() {
+        let app = Instance::new();
+        app.add_user("alice");
+        app.add_user("bob");
+        let login = |body: &'static str| {
+            Request::post("/greader/accounts/ClientLogin")
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let (status, _) = app.send(login("Email=alice&Passwd=bob-password")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = app.send(login("Email=alice&Passwd=alice-password")).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = body.lines().find_map(|line| line.strip_prefix("Auth=")).unwrap();
+
+        let uri = "/greader/reader/api/0/subscription/list";
+        let (status, _) = app.get_json(uri, "GoogleLogin auth=wrong").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (_, res) = app.get_json(uri, &format!("GoogleLogin auth={token}")).await;
+        let subs = res["subscriptions"].as_array().unwrap();
+        assert_eq!(subs.len(), 1);
+        assert_eq!(subs[0]["title"], "alice");
+    }
+
+    #[tokio::test]
+    async fn nextcloud_only_exposes_the_data_of_the_user() {
+        let app = Instance::new();
+        let alice_entry = app.add_user("alice");
+        let bob_entry = app.add_user("bob");
+        let alice = basic("alice", "alice-password");
+        let api = "/nextcloud/index.php/apps/news/api/v1-3";
+
+        let (status, _) = app.get_json(&format!("{api}/feeds"), &basic("alice", "wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (_, res) = app.get_json(&format!("{api}/feeds"), &alice).await;
+        assert_eq!(res["feeds"].as_array().unwrap().len(), 1);
+        let (_, res) = app.get_json(&format!("{api}/items"), &alice).await;
+        assert_eq!(ids(&res["items"]), vec![app.seq(alice_entry)]);
+
+        let uri = format!("{api}/items/{}/star", app.seq(bob_entry));
+        let (status, _) = app.send_json(Method::POST, &uri, &alice, json!({})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(app.repo("alice").get_starred().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn miniflux_only_exposes_the_data_of_the_user() {
+        let app = Instance::new();
+        let alice_entry = app.add_user("alice");
+        let bob_entry = app.add_user("bob");
+        let alice = basic("alice", "alice-password");
+
+        let (_, feeds) = app.get_json("/miniflux/v1/feeds", &alice).await;
+        assert_eq!(feeds.as_array().unwrap().len(), 1);
+        let uri = format!("/miniflux/v1/entries/{}", app.seq(alice_entry));
+        let (status, _) = app.get_json(&uri, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        let uri = format!("/miniflux/v1/entries/{}", app.seq(bob_entry));
+        let (status, _) = app.get_json(&uri, &alice).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let uri = format!("/miniflux/v1/entries/{}/bookmark", app.seq(bob_entry));
+        app.send_json(Method::PUT, &uri, &alice, json!({})).await;
+        let update = json!({ "entry_ids": [app.seq(bob_entry)], "status": "unread" });
+        app.send_json(Method::PUT, "/miniflux/v1/entries", &alice, update).await;
+        assert!(app.repo("alice").get_starred().unwrap().is_empty());
+        assert_eq!(app.repo("alice").get_unread().unwrap().len(), 1);
+    }
+}
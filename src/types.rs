@@ -1,3 +1,4 @@
+use std::fmt;
 use std::num::ParseIntError;
 use std::str::FromStr;
 
@@ -6,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
 use crate::codecs;
+use crate::extract::Article;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Subscription<'a> {
@@ -29,6 +31,18 @@ impl<'a> Subscription<'a> {
             created_at,
         }
     }
+
+    // the pseudo-feed that holds pages saved by the user
+    pub fn pages(created_at: OffsetDateTime) -> Self {
+        Subscription {
+            id: FeedId::PAGES,
+            feed_id: FeedId::PAGES,
+            title: "Pages",
+            feed_url: "",
+            site_url: "",
+            created_at,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -85,6 +99,293 @@ impl<'a> Entry<'a> {
         };
         Some(res)
     }
+
+    pub fn from_page(
+        url: &'a str,
+        title: Option<&'a str>,
+        content: Option<&'a str>,
+        created_at: OffsetDateTime,
+    ) -> Self {
+        Entry {
+            id: EntryId::from_ident_and_date(url, created_at),
+            feed_id: FeedId::PAGES,
+            title,
+            url: Some(url),
+            extracted_content_url: None,
+            author: None,
+            content,
+            summary: None,
+            published: created_at,
+            created_at,
+            image: None,
+        }
+    }
+}
+
+// item data not included in the basic Feedbin entry representation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntryExtras<'a> {
+    #[serde(borrow)]
+    pub enclosure: Option<Enclosure<'a>>,
+    #[serde(borrow)]
+    pub original: Original<'a>,
+}
+
+impl<'a> EntryExtras<'a> {
+    // podcasts use the RSS enclosure, Media RSS content is used when there is none
+    pub fn from_item(entry: &Entry<'a>, item: &Item<'a>) -> Self {
+        let media = item.media.iter().find(|media| {
+            let is_media = matches!(media.medium, Some(ContentMedium::Audio | ContentMedium::Video))
+                || matches!(media.mime_type, Some(str) if str.starts_with("audio/") || str.starts_with("video/"));
+            is_media && media.url.is_some()
+        });
+        let enclosure = item
+            .enclosure
+            .as_ref()
+            .and_then(|enclosure| {
+                enclosure.url.map(|url| Enclosure {
+                    enclosure_url: url,
+                    enclosure_type: enclosure.mime_type,
+                    enclosure_length: enclosure.length,
+                    itunes_duration: media.and_then(|media| media.duration),
+                })
+            })
+            .or_else(|| {
+                media.and_then(|media| {
+                    media.url.map(|url| Enclosure {
+                        enclosure_url: url,
+                        enclosure_type: media.mime_type,
+                        enclosure_length: media.file_size,
+                        itunes_duration: media.duration,
+                    })
+                })
+            });
+        Self {
+            enclosure,
+            ..Self::from_entry(entry)
+        }
+    }
+
+    pub fn from_entry(entry: &Entry<'a>) -> Self {
+        let original = Original {
+            entry_id: entry.id,
+            title: entry.title,
+            url: entry.url,
+            author: entry.author,
+            content: None,
+            published: entry.published,
+        };
+        Self {
+            enclosure: None,
+            original,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Enclosure<'a> {
+    pub enclosure_url: &'a str,
+    pub enclosure_type: Option<&'a str>,
+    pub enclosure_length: Option<&'a str>,
+    pub itunes_duration: Option<&'a str>,
+}
+
+impl<'a> Enclosure<'a> {
+    // the length in bytes, feeds often leave it empty or set it to zero
+    pub fn size(&self) -> u64 {
+        self.enclosure_length
+            .and_then(|str| str.trim().parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Original<'a> {
+    pub entry_id: EntryId,
+    pub title: Option<&'a str>,
+    pub url: Option<&'a str>,
+    pub author: Option<&'a str>,
+    // only stored when the entry content was replaced, the entry content is the original otherwise
+    pub content: Option<&'a str>,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub published: OffsetDateTime,
+}
+
+impl<'a> Original<'a> {
+    pub fn with_content(&self, entry: &Entry<'a>) -> Self {
+        Self {
+            content: self.content.or(entry.content),
+            ..*self
+        }
+    }
+}
+
+// the representation of an entry returned by the API, it includes the optional fields
+// of the Feedbin extended mode and points the extracted content URL at this service
+#[derive(Debug, Serialize)]
+pub struct EntryView<'a> {
+    pub id: EntryId,
+    pub feed_id: FeedId,
+    pub title: Option<&'a str>,
+    pub url: Option<&'a str>,
+    pub extracted_content_url: Option<String>,
+    pub author: Option<&'a str>,
+    pub content: Option<&'a str>,
+    pub summary: Option<&'a str>,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub published: OffsetDateTime,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub created_at: OffsetDateTime,
+    pub images: Option<&'a Image<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enclosure: Option<&'a Enclosure<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<Original<'a>>,
+}
+
+impl<'a> EntryView<'a> {
+    pub fn new(entry: &'a Entry<'a>, base_url: &str) -> Self {
+        Self {
+            id: entry.id,
+            feed_id: entry.feed_id,
+            title: entry.title,
+            url: entry.url,
+            extracted_content_url: entry.url.map(|_| format!("{base_url}/extract/{}", entry.id)),
+            author: entry.author,
+            content: entry.content,
+            summary: entry.summary,
+            published: entry.published,
+            created_at: entry.created_at,
+            images: entry.image.as_ref(),
+            enclosure: None,
+            original: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractedArticle<'a> {
+    pub url: &'a str,
+    pub title: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub content: &'a str,
+    pub excerpt: &'a str,
+    pub word_count: usize,
+}
+
+impl<'a> ExtractedArticle<'a> {
+    pub fn from_article(article: &'a Article) -> Self {
+        Self {
+            url: &article.url,
+            title: article.title.as_deref(),
+            author: article.author.as_deref(),
+            content: &article.content,
+            excerpt: &article.excerpt,
+            word_count: article.word_count,
+        }
+    }
+}
+
+// per-subscription settings that are not part of the Feedbin API
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct FeedOptions {
+    // replace the content of new entries with the article extracted from their page
+    pub extract_content: bool,
+}
+
+// the entries published by a shared Atom feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedSource {
+    Starred,
+    Tag(String),
+    SavedSearch(SavedSearchId),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Account<'a> {
+    pub name: &'a str,
+    // an Argon2 hash in the PHC string format
+    pub password_hash: &'a str,
+    // the Fever API authenticates with the MD5 hash of `user:password`, it's derived from
+    // a separate Fever password and empty until one is set
+    pub fever_key: &'a str,
+    // issued to Google Reader clients on login, reset along with the password
+    pub reader_token: &'a str,
+    pub disabled: bool,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub created_at: OffsetDateTime,
+}
+
+// an account without its credentials
+#[derive(Debug, Serialize)]
+pub struct UserView<'a> {
+    pub name: &'a str,
+    pub disabled: bool,
+    // whether the user has an admin password
+    pub admin: bool,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub created_at: OffsetDateTime,
+}
+
+impl<'a> UserView<'a> {
+    pub fn new(account: &Account<'a>, admin: bool) -> Self {
+        Self {
+            name: account.name,
+            disabled: account.disabled,
+            admin,
+            created_at: account.created_at,
+        }
+    }
+}
+
+// what credentials or an API token grant access to, each scope includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Read,
+    Write,
+    Admin,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiToken<'a> {
+    pub user: &'a str,
+    pub name: &'a str,
+    pub scope: TokenScope,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub created_at: OffsetDateTime,
+}
+
+// the secret token is only included when the token is created
+#[derive(Debug, Serialize)]
+pub struct ApiTokenView<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub scope: TokenScope,
+    #[serde(with = "codecs::rfc3339_date")]
+    pub created_at: OffsetDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl<'a> ApiTokenView<'a> {
+    pub fn new(id: &'a str, token: &ApiToken<'a>) -> Self {
+        Self {
+            id,
+            name: token.name,
+            scope: token.scope,
+            created_at: token.created_at,
+            token: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SharedFeed {
+    pub token: String,
+    pub source: FeedSource,
+    pub url: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -100,6 +401,52 @@ impl<'a> Tagging<'a> {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSearch<'a> {
+    pub id: SavedSearchId,
+    pub name: &'a str,
+    pub query: &'a str,
+}
+
+impl<'a> SavedSearch<'a> {
+    pub fn new(id: SavedSearchId, name: &'a str, query: &'a str) -> Self {
+        Self { id, name, query }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub feed_count: usize,
+    pub unread_count: usize,
+}
+
+// tags have no IDs, APIs that need them use a hash of the name, zero is never used
+pub fn tag_id(name: &str) -> u32 {
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    (hash & 0x7fffffff).max(1)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Icon<'a> {
+    pub content_type: &'a str,
+    pub data: &'a [u8],
+}
+
+impl<'a> Icon<'a> {
+    pub fn new(content_type: &'a str, data: &'a [u8]) -> Self {
+        Self { content_type, data }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IconLink {
+    pub host: String,
+    pub url: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Image<'a> {
     #[serde(rename = "original_url")]
@@ -119,8 +466,32 @@ impl FromStr for TaggingId {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearchId(pub(super) u64);
+
+impl FromStr for SavedSearchId {
+    type Err = ParseIntError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl fmt::Display for SavedSearchId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FeedId(pub(super) u64);
 
+impl FeedId {
+    // reserved for the pages pseudo-feed, generated IDs count up from zero, it's the largest
+    // integer that clients decoding IDs as signed 64-bit integers or JavaScript numbers agree on
+    pub const PAGES: FeedId = FeedId((1 << 53) - 1);
+}
+
 impl FromStr for FeedId {
     type Err = ParseIntError;
 
@@ -130,8 +501,23 @@ impl FromStr for FeedId {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub struct EntryId(u64);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EntryId(pub(super) u64);
+
+impl FromStr for EntryId {
+    type Err = ParseIntError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 impl EntryId {
     // the date is stored as a unix timestamp in the first 4 bytes of the ID
@@ -142,6 +528,12 @@ impl EntryId {
         bytes[4..6].copy_from_slice(&fletcher16(name.as_bytes()).to_be_bytes());
         EntryId(u64::from_ne_bytes(bytes))
     }
+
+    // the bytes the ID was built from, comparing them orders entries by date
+    // regardless of the byte order of the platform
+    pub fn date_key(&self) -> [u8; 8] {
+        self.0.to_ne_bytes()
+    }
 }
 
 fn fletcher16(bytes: &[u8]) -> u16 {